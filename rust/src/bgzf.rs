@@ -0,0 +1,67 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Whether the file starts with a BGZF block: a gzip member whose extra
+/// field carries the `BC` block-size subfield.
+pub fn is_bgzf(path: &Path) -> io::Result<bool> {
+    let mut header = [0u8; 18];
+    match File::open(path)?.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(false),
+        Err(e) => return Err(e),
+    }
+    Ok(header[..4] == [31, 139, 8, 4] && header[12..14] == *b"BC")
+}
+
+/// Compress `data` as a BGZF stream, in blocks of up to `block_len` input
+/// bytes followed by the empty end-of-file block, as `bgzip` writes it.
+#[cfg(test)]
+pub(crate) fn compress(data: &[u8], block_len: usize) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut output = Vec::new();
+    for chunk in data.chunks(block_len.max(1)).chain([&[][..]]) {
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(chunk).unwrap();
+        let deflated = encoder.finish().unwrap();
+        let mut crc = flate2::Crc::new();
+        crc.update(chunk);
+
+        let block_size = (18 + deflated.len() + 8 - 1) as u16;
+        output.extend_from_slice(&[31, 139, 8, 4, 0, 0, 0, 0, 0, 255, 6, 0, b'B', b'C', 2, 0]);
+        output.extend_from_slice(&block_size.to_le_bytes());
+        output.extend_from_slice(&deflated);
+        output.extend_from_slice(&crc.sum().to_le_bytes());
+        output.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bgzip_output_is_bgzf() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.gz");
+        std::fs::write(&path, compress(b"##fileformat=VCFv4.2\n", 1024)).unwrap();
+
+        assert!(is_bgzf(&path).unwrap());
+    }
+
+    #[test]
+    fn plain_gzip_is_not_bgzf() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plain.gz");
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"##fileformat=VCFv4.2\n").unwrap();
+        std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+        assert!(!is_bgzf(&path).unwrap());
+    }
+}
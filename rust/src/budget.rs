@@ -0,0 +1,54 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// A ceiling on the bytes a single run may download, shared by every file it
+/// fetches.
+#[derive(Debug)]
+pub struct ByteBudget {
+    limit: u64,
+    used: AtomicU64,
+    exhausted: AtomicBool,
+}
+
+impl ByteBudget {
+    pub fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            used: AtomicU64::new(0),
+            exhausted: AtomicBool::new(false),
+        }
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    pub fn used(&self) -> u64 {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    /// Whether a download was refused or cut short for lack of budget. No new
+    /// downloads should start once this is set.
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted.load(Ordering::Relaxed)
+    }
+
+    /// Check that `bytes` more would still fit, without consuming them.
+    pub fn fits(&self, bytes: u64) -> bool {
+        let fits = self.used().saturating_add(bytes) <= self.limit;
+        if !fits {
+            self.exhausted.store(true, Ordering::Relaxed);
+        }
+        fits
+    }
+
+    /// Record `bytes` as downloaded, returning false if that goes over the
+    /// limit.
+    pub fn consume(&self, bytes: u64) -> bool {
+        let used = self.used.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        if used > self.limit {
+            self.exhausted.store(true, Ordering::Relaxed);
+            return false;
+        }
+        true
+    }
+}
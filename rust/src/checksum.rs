@@ -0,0 +1,389 @@
+use anyhow::Context;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::config::DatabaseFiles;
+use crate::downloader::{
+    calculate_checksum_decompressed, check_checksum, checksum_matches, parse_checksum_file,
+    Downloader, RequestOptions,
+};
+use crate::{Error, Result};
+
+/// Where a database's expected VCF checksum is published.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumSource {
+    /// The `md5` URL is a sidecar file of the form `<hash>  <path>`.
+    #[default]
+    Sidecar,
+    /// The `md5` URL is a combined listing (e.g. `CHECKSUMS`) with one
+    /// `<hash>  <path>` line per file; the line for the VCF is used.
+    Combined,
+    /// The base64 `Content-MD5` header on the VCF response.
+    ContentMd5,
+    /// The VCF's `ETag`, which S3 sets to the MD5 for single-part uploads.
+    Etag,
+}
+
+/// Which bytes a published checksum covers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumTarget {
+    /// The gzip file as downloaded.
+    #[default]
+    Compressed,
+    /// The uncompressed VCF, for mirrors that hash it before compressing.
+    Decompressed,
+}
+
+impl ChecksumTarget {
+    pub fn is_compressed(&self) -> bool {
+        matches!(self, ChecksumTarget::Compressed)
+    }
+}
+
+/// How a downloaded VCF is checked against its published checksum.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChecksumMethod {
+    pub algorithm: HashAlgorithm,
+    pub target: ChecksumTarget,
+}
+
+impl ChecksumMethod {
+    /// Check the downloaded file at `path` against `expected`, hashing
+    /// whichever content the target says the checksum covers.
+    pub fn verify(&self, path: &Path, expected: &str) -> Result<bool> {
+        match self.check(path, expected) {
+            Ok(()) => Ok(true),
+            Err(Error::ChecksumMismatch { .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Like [`verify`](Self::verify), but a mismatch is an
+    /// [`Error::ChecksumMismatch`] carrying both digests.
+    pub fn check(&self, path: &Path, expected: &str) -> Result<()> {
+        match self.target {
+            ChecksumTarget::Compressed => check_checksum(path, expected, self.algorithm),
+            ChecksumTarget::Decompressed => {
+                let actual = calculate_checksum_decompressed(path, self.algorithm)?;
+                checksum_matches(path, expected, actual)
+            }
+        }
+    }
+}
+
+/// Hash algorithms a published or user-supplied checksum may use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    #[default]
+    Md5,
+    Sha256,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    pub fn is_md5(&self) -> bool {
+        matches!(self, HashAlgorithm::Md5)
+    }
+
+    /// Length of the algorithm's digest in hex digits.
+    pub fn hex_len(&self) -> usize {
+        match self {
+            HashAlgorithm::Md5 => 32,
+            HashAlgorithm::Sha256 => 64,
+            HashAlgorithm::Sha512 => 128,
+        }
+    }
+
+    /// Pick the algorithm from the length of a hex digest: 32 digits is MD5,
+    /// 64 SHA-256 and 128 SHA-512. Anything else, or non-hex input, is an
+    /// error.
+    pub fn infer(hash: &str) -> Result<Self> {
+        if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow::anyhow!("'{}' is not a hexadecimal digest", hash).into());
+        }
+
+        [HashAlgorithm::Md5, HashAlgorithm::Sha256, HashAlgorithm::Sha512]
+            .into_iter()
+            .find(|algorithm| algorithm.hex_len() == hash.len())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Can't infer the algorithm of a {}-digit hash; expected 32 (MD5), 64 (SHA-256) or 128 (SHA-512)",
+                    hash.len()
+                )
+                .into()
+            })
+    }
+
+    /// Check that `hash` is a hex digest of this algorithm's length.
+    pub fn validate(&self, hash: &str) -> Result<()> {
+        if hash.len() != self.hex_len() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow::anyhow!(
+                "Expected a {}-digit hexadecimal {} digest",
+                self.hex_len(),
+                self
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            HashAlgorithm::Md5 => "MD5",
+            HashAlgorithm::Sha256 => "SHA-256",
+            HashAlgorithm::Sha512 => "SHA-512",
+        })
+    }
+}
+
+impl ChecksumSource {
+    /// Whether the `md5` URL is a per-file sidecar worth keeping next to the VCF.
+    pub fn is_sidecar(&self) -> bool {
+        matches!(self, ChecksumSource::Sidecar)
+    }
+
+    /// Fetch the expected checksum of the VCF and, when the source carries
+    /// one, the release date embedded in the published filename.
+    pub async fn fetch(
+        &self,
+        downloader: &Downloader,
+        files: &DatabaseFiles,
+        options: &RequestOptions,
+    ) -> Result<(String, Option<String>)> {
+        match self {
+            ChecksumSource::Sidecar => {
+                let content = downloader
+                    .download_text_with_options(&files.md5, options)
+                    .await
+                    .context("Failed to download checksum file")?;
+                parse_checksum_file(&content, files.checksum_algo)
+            }
+            ChecksumSource::Combined => {
+                let content = downloader
+                    .download_text_with_options(&files.md5, options)
+                    .await
+                    .context("Failed to download checksum listing")?;
+                let vcf_name = files.vcf.rsplit('/').next().unwrap_or(&files.vcf);
+                let line = content
+                    .lines()
+                    .find(|line| {
+                        line.split_whitespace()
+                            .nth(1)
+                            .is_some_and(|path| path.rsplit('/').next() == Some(vcf_name))
+                    })
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("No entry for {} in checksum listing", vcf_name)
+                    })?;
+                parse_checksum_file(line, files.checksum_algo)
+            }
+            ChecksumSource::ContentMd5 => {
+                let headers = downloader.head(&files.vcf, options).await?;
+                let value = header_str(&headers, "content-md5")?;
+                let digest = base64::engine::general_purpose::STANDARD
+                    .decode(value.trim())
+                    .context("Content-MD5 header is not valid base64")?;
+                if digest.len() != 16 {
+                    return Err(anyhow::anyhow!("Content-MD5 header is not an MD5 digest").into());
+                }
+                let hex = digest.iter().map(|b| format!("{:02x}", b)).collect();
+                Ok((hex, None))
+            }
+            ChecksumSource::Etag => {
+                let headers = downloader.head(&files.vcf, options).await?;
+                let etag = header_str(&headers, "etag")?
+                    .trim_start_matches("W/")
+                    .trim_matches('"')
+                    .to_string();
+                if etag.len() != 32 || !etag.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(anyhow::anyhow!(
+                        "ETag '{}' is not an MD5 (multipart uploads are not supported)",
+                        etag
+                    )
+                    .into());
+                }
+                Ok((etag, None))
+            }
+        }
+    }
+}
+
+fn header_str<'a>(headers: &'a reqwest::header::HeaderMap, name: &str) -> Result<&'a str> {
+    headers
+        .get(name)
+        .ok_or_else(|| anyhow::anyhow!("Server did not send a {} header", name))?
+        .to_str()
+        .with_context(|| format!("{} header is not valid text", name))
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_config;
+    use crate::test_server::{Response, TestServer};
+
+    /// MD5 of `hello`.
+    const HELLO_MD5: &str = "5d41402abc4b2a76b9719d911017c592";
+    /// SHA-256 of `hello`.
+    const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+    /// SHA-512 of `hello`.
+    const HELLO_SHA512: &str = "9b71d224bd62f3785d96d46ad3ea3d73319bfbc2890caadae2dff72519673ca72323c3d99ba5c11d7c7acc6e14b8c5da0c4663475c2e5c3adef46f73bcdec043";
+
+    /// The catalog entry for a VCF and checksum served by `server`.
+    fn files(server: &TestServer, source: &str) -> DatabaseFiles {
+        let yaml = format!(
+            "clinvar:\n  GRCh38:\n    vcf: {}\n    tbi: {}\n    md5: {}\n    checksum_source: {}\n",
+            server.url("/clinvar_20240115.vcf.gz"),
+            server.url("/clinvar_20240115.vcf.gz.tbi"),
+            server.url("/checksums"),
+            source
+        );
+        let mut config = parse_config(&yaml).unwrap();
+        config.remove("clinvar").unwrap().remove("GRCh38").unwrap()
+    }
+
+    async fn fetch(
+        server: &TestServer,
+        source: ChecksumSource,
+    ) -> Result<(String, Option<String>)> {
+        let downloader = Downloader::new()?.with_quiet();
+        let files = files(server, serde_yaml::to_string(&source)?.trim());
+        source
+            .fetch(&downloader, &files, &RequestOptions::default())
+            .await
+    }
+
+    #[tokio::test]
+    async fn sidecar_source_reads_hash_and_date() {
+        let server = TestServer::start(|_| {
+            Response::new(200, format!("{}  clinvar_20240115.vcf.gz\n", HELLO_MD5))
+        })
+        .await;
+
+        let (hash, date) = fetch(&server, ChecksumSource::Sidecar).await.unwrap();
+
+        assert_eq!(hash, HELLO_MD5);
+        assert_eq!(date.as_deref(), Some("20240115"));
+        assert_eq!(server.requests()[0].path, "/checksums");
+    }
+
+    #[tokio::test]
+    async fn combined_source_picks_the_vcf_line() {
+        let server = TestServer::start(|_| {
+            Response::new(
+                200,
+                format!(
+                    "{}  README\n{}  ./clinvar_20240115.vcf.gz\n",
+                    "0".repeat(32),
+                    HELLO_MD5
+                ),
+            )
+        })
+        .await;
+
+        let (hash, _) = fetch(&server, ChecksumSource::Combined).await.unwrap();
+
+        assert_eq!(hash, HELLO_MD5);
+    }
+
+    #[tokio::test]
+    async fn content_md5_source_decodes_the_header() {
+        let server = TestServer::start(|_| {
+            Response::new(200, "hello").header("Content-MD5", "XUFAKrxLKna5cZ2REBfFkg==")
+        })
+        .await;
+
+        let (hash, date) = fetch(&server, ChecksumSource::ContentMd5).await.unwrap();
+
+        assert_eq!(hash, HELLO_MD5);
+        assert_eq!(date, None);
+        let request = &server.requests()[0];
+        assert_eq!(
+            (request.method.as_str(), request.path.as_str()),
+            ("HEAD", "/clinvar_20240115.vcf.gz")
+        );
+    }
+
+    #[tokio::test]
+    async fn etag_source_strips_quotes() {
+        let server = TestServer::start(|_| {
+            Response::new(200, "hello").header("ETag", &format!("\"{}\"", HELLO_MD5))
+        })
+        .await;
+
+        let (hash, _) = fetch(&server, ChecksumSource::Etag).await.unwrap();
+
+        assert_eq!(hash, HELLO_MD5);
+    }
+
+    #[tokio::test]
+    async fn header_sources_require_the_header() {
+        let server = TestServer::start(|_| Response::new(200, "hello")).await;
+
+        let error = fetch(&server, ChecksumSource::ContentMd5)
+            .await
+            .unwrap_err();
+
+        assert!(error.to_string().contains("content-md5"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn etag_source_rejects_multipart_etags() {
+        let server = TestServer::start(|_| {
+            Response::new(200, "hello").header("ETag", &format!("\"{}-3\"", HELLO_MD5))
+        })
+        .await;
+
+        assert!(fetch(&server, ChecksumSource::Etag).await.is_err());
+    }
+
+    #[test]
+    fn infer_picks_the_algorithm_by_length() {
+        assert_eq!(HashAlgorithm::infer(HELLO_MD5).unwrap(), HashAlgorithm::Md5);
+        assert_eq!(
+            HashAlgorithm::infer(HELLO_SHA256).unwrap(),
+            HashAlgorithm::Sha256
+        );
+        assert_eq!(
+            HashAlgorithm::infer(HELLO_SHA512).unwrap(),
+            HashAlgorithm::Sha512
+        );
+    }
+
+    #[test]
+    fn inferred_algorithm_verifies_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hello");
+        std::fs::write(&path, "hello").unwrap();
+
+        for hash in [HELLO_MD5, HELLO_SHA256, HELLO_SHA512] {
+            let method = ChecksumMethod {
+                algorithm: HashAlgorithm::infer(hash).unwrap(),
+                target: ChecksumTarget::Compressed,
+            };
+            assert!(method.verify(&path, hash).unwrap(), "{}", hash);
+        }
+    }
+
+    #[test]
+    fn infer_rejects_ambiguous_lengths() {
+        // 40 digits is SHA-1, which isn't supported.
+        let error = HashAlgorithm::infer(&"a".repeat(40)).unwrap_err();
+
+        assert!(error.to_string().contains("40-digit"), "{}", error);
+    }
+
+    #[test]
+    fn infer_rejects_malformed_hashes() {
+        assert!(HashAlgorithm::infer("").is_err());
+        assert!(HashAlgorithm::infer(&"g".repeat(32)).is_err());
+        assert!(HashAlgorithm::infer(&format!(" {}", &HELLO_MD5[1..])).is_err());
+    }
+}
@@ -1,8 +1,30 @@
+use anyhow::Context;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::checksum::{ChecksumMethod, ChecksumSource, ChecksumTarget, HashAlgorithm};
+use crate::downloader::RequestOptions;
+use crate::host_limits::HostLimits;
 
 const DATABASES_YAML: &str = include_str!("databases.yaml");
 
+/// Environment variable naming a catalog file to merge over the built-in
+/// one.
+pub const CONFIG_ENV: &str = "GLADE_CONFIG";
+
+/// Environment variable naming the directory used in place of `~/.glade`.
+pub const HOME_ENV: &str = "GLADE_HOME";
+
+/// glade's own directory: `GLADE_HOME` when set, otherwise `~/.glade`.
+pub fn glade_home() -> Option<PathBuf> {
+    match std::env::var_os(HOME_ENV).filter(|path| !path.is_empty()) {
+        Some(path) => Some(PathBuf::from(path)),
+        None => dirs::home_dir().map(|home| home.join(".glade")),
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DatabaseConfig {
     #[serde(flatten)]
@@ -17,11 +39,727 @@ pub struct DatabaseVersions {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DatabaseFiles {
+    /// Alias accepted in place of `--database`/`--genome-version`, e.g. `cv38`.
+    /// Must be unique across the catalog.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_name: Option<String>,
     pub vcf: String,
-    pub tbi: String,
+    /// Alternate URLs for the VCF, tried in order when `vcf` fails to
+    /// download or verify.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vcf_mirrors: Vec<String>,
+    /// Tabix index. Either this or `csi` must be given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tbi: Option<String>,
+    /// CSI index, for VCFs with contigs too long for a `.tbi` (over 2^29 bp).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub csi: Option<String>,
     pub md5: String,
+    /// Extra request headers sent with every download for this version, e.g.
+    /// an `Accept` value a picky mirror insists on.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    /// Politeness limits for the hosts serving this version's files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_limits: Option<HostLimits>,
+    /// Request timeout in seconds for this version's files, overriding the
+    /// downloader's default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Retries after a transient failure for this version's files,
+    /// overriding the downloader's retry policy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+    /// Seconds a newly downloaded release waits before the stable symlinks
+    /// point at it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub promote_delay: Option<u64>,
+    /// Set to false for mirrors that reject HEAD; headers are then probed
+    /// with a one-byte ranged GET.
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub supports_head: bool,
+    /// Force HTTP/1.1 for this version's mirror.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub http1_only: bool,
+    /// Where the expected VCF checksum comes from; the `md5` URL is a sidecar
+    /// file unless configured otherwise.
+    #[serde(default, skip_serializing_if = "ChecksumSource::is_sidecar")]
+    pub checksum_source: ChecksumSource,
+    /// Whether the published checksum covers the gzip file or the VCF inside
+    /// it.
+    #[serde(default, skip_serializing_if = "ChecksumTarget::is_compressed")]
+    pub checksum_target: ChecksumTarget,
+    /// Algorithm of the published checksum: `md5` (the default), `sha256`
+    /// or `sha512`.
+    #[serde(default, skip_serializing_if = "HashAlgorithm::is_md5")]
+    pub checksum_algo: HashAlgorithm,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+impl DatabaseFiles {
+    /// URLs to try for the file at `url`: the VCF's mirrors follow it, every
+    /// other file has just the one.
+    pub fn urls_for<'a>(&'a self, url: &'a str) -> Vec<&'a str> {
+        let mut urls = vec![url];
+        if url == self.vcf {
+            urls.extend(self.vcf_mirrors.iter().map(String::as_str));
+        }
+        urls
+    }
+
+    /// Index files to fetch alongside the VCF, as (description, URL, file
+    /// name). The `.tbi` comes first when both are configured.
+    pub fn indexes(&self) -> Vec<(&'static str, &str, &'static str)> {
+        let mut indexes = Vec::new();
+        if let Some(tbi) = &self.tbi {
+            indexes.push(("TBI", tbi.as_str(), "clinvar.vcf.gz.tbi"));
+        }
+        if let Some(csi) = &self.csi {
+            indexes.push(("CSI", csi.as_str(), "clinvar.vcf.gz.csi"));
+        }
+        indexes
+    }
+
+    pub fn checksum_method(&self) -> ChecksumMethod {
+        ChecksumMethod {
+            algorithm: self.checksum_algo,
+            target: self.checksum_target,
+        }
+    }
+
+    pub fn request_options(&self) -> crate::Result<RequestOptions> {
+        if self.timeout == Some(0) {
+            return Err(anyhow::anyhow!("timeout must be at least 1 second").into());
+        }
+
+        Ok(RequestOptions {
+            headers: self.headers.clone(),
+            timeout: self.timeout.map(Duration::from_secs),
+            retries: self.retries,
+            http1_only: self.http1_only,
+        })
+    }
 }
 
+/// The catalog file in effect: `GLADE_CONFIG` when set, otherwise
+/// `databases.yaml` in [`glade_home`] if it exists. `None` means the built-in
+/// catalog.
+pub fn config_path() -> Option<PathBuf> {
+    config_path_from(None)
+}
+
+/// Like [`config_path`], but `explicit` (e.g. from `--config`) wins over
+/// both.
+pub fn config_path_from(explicit: Option<&Path>) -> Option<PathBuf> {
+    if let Some(path) = explicit {
+        return Some(path.to_path_buf());
+    }
+    if let Some(path) = std::env::var_os(CONFIG_ENV).filter(|path| !path.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+
+    glade_home()
+        .map(|home| home.join("databases.yaml"))
+        .filter(|path| path.is_file())
+}
+
+/// Load the built-in catalog with the file at [`config_path`], if any,
+/// merged over it.
 pub fn load_config() -> crate::Result<HashMap<String, HashMap<String, DatabaseFiles>>> {
-    serde_yaml::from_str(DATABASES_YAML).map_err(Into::into)
+    load_config_from(None)
+}
+
+/// Like [`load_config`], but merging the file at `explicit` when given.
+pub fn load_config_from(
+    explicit: Option<&Path>,
+) -> crate::Result<HashMap<String, HashMap<String, DatabaseFiles>>> {
+    let Some(path) = config_path_from(explicit) else {
+        return parse_config(DATABASES_YAML);
+    };
+
+    let yaml = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read catalog {}", path.display()))?;
+    merge_configs(DATABASES_YAML, &yaml).map_err(|e| {
+        anyhow::Error::new(e)
+            .context(format!("Invalid catalog {}", path.display()))
+            .into()
+    })
+}
+
+/// Parse and validate a catalog in the `databases.yaml` format.
+pub fn parse_config(yaml: &str) -> crate::Result<HashMap<String, HashMap<String, DatabaseFiles>>> {
+    check_duplicate_keys(yaml)?;
+    let config = serde_yaml::from_str(yaml)?;
+    validate(&config)?;
+    Ok(config)
+}
+
+/// Parse a user catalog and deep-merge it over `default`. Mappings merge key
+/// by key, so the user file can add a database or version, or override a
+/// single field such as `vcf`; any other value, lists included, replaces the
+/// default outright. The merged catalog is validated as a whole.
+pub fn merge_configs(
+    default: &str,
+    user: &str,
+) -> crate::Result<HashMap<String, HashMap<String, DatabaseFiles>>> {
+    check_duplicate_keys(default)?;
+    check_duplicate_keys(user)?;
+    let mut merged: serde_yaml::Value = serde_yaml::from_str(default)?;
+    merge_values(&mut merged, serde_yaml::from_str(user)?);
+
+    let config = serde_yaml::from_value(merged)?;
+    validate(&config)?;
+    Ok(config)
+}
+
+fn merge_values(base: &mut serde_yaml::Value, overlay: serde_yaml::Value) {
+    match (base, overlay) {
+        (_, serde_yaml::Value::Null) => {}
+        (serde_yaml::Value::Mapping(base), serde_yaml::Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn validate(config: &HashMap<String, HashMap<String, DatabaseFiles>>) -> crate::Result<()> {
+    validate_short_names(config)?;
+    validate_indexes(config)?;
+    validate_checksums(config)?;
+    validate_urls(config)
+}
+
+/// A catalog URL that can't be downloaded from.
+#[derive(Debug, Clone)]
+pub struct InvalidUrl {
+    pub database: String,
+    pub genome_version: String,
+    /// The offending field, e.g. `vcf` or `vcf_mirrors[1]`.
+    pub field: String,
+    pub url: String,
+    pub reason: String,
+}
+
+impl std::fmt::Display for InvalidUrl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{} {}: '{}' {}",
+            self.database, self.genome_version, self.field, self.url, self.reason
+        )
+    }
+}
+
+/// Every URL in the catalog that isn't an absolute http or https URL, sorted
+/// by database, version and field. FTP isn't accepted because the downloader
+/// can't fetch it.
+pub fn invalid_urls(config: &HashMap<String, HashMap<String, DatabaseFiles>>) -> Vec<InvalidUrl> {
+    let mut invalid = Vec::new();
+
+    for (db_name, versions) in config {
+        for (genome_version, files) in versions {
+            let mut fields = vec![("vcf".to_string(), &files.vcf)];
+            for (index, mirror) in files.vcf_mirrors.iter().enumerate() {
+                fields.push((format!("vcf_mirrors[{}]", index), mirror));
+            }
+            if let Some(tbi) = &files.tbi {
+                fields.push(("tbi".to_string(), tbi));
+            }
+            if let Some(csi) = &files.csi {
+                fields.push(("csi".to_string(), csi));
+            }
+            // Header-based checksum sources never fetch the md5 URL, so it
+            // may be left empty.
+            let md5_unused = matches!(
+                files.checksum_source,
+                ChecksumSource::ContentMd5 | ChecksumSource::Etag
+            );
+            if !(md5_unused && files.md5.is_empty()) {
+                fields.push(("md5".to_string(), &files.md5));
+            }
+
+            for (field, url) in fields {
+                let reason = match reqwest::Url::parse(url) {
+                    Ok(parsed) if !matches!(parsed.scheme(), "http" | "https") => {
+                        format!("uses unsupported scheme '{}'", parsed.scheme())
+                    }
+                    Ok(parsed) if parsed.host_str().is_none_or(str::is_empty) => {
+                        "has no host".to_string()
+                    }
+                    Ok(_) => continue,
+                    Err(e) => format!("is not a valid URL ({})", e),
+                };
+                invalid.push(InvalidUrl {
+                    database: db_name.clone(),
+                    genome_version: genome_version.clone(),
+                    field,
+                    url: url.clone(),
+                    reason,
+                });
+            }
+        }
+    }
+
+    invalid.sort_by(|a, b| {
+        (&a.database, &a.genome_version, &a.field).cmp(&(&b.database, &b.genome_version, &b.field))
+    });
+    invalid
+}
+
+fn validate_urls(config: &HashMap<String, HashMap<String, DatabaseFiles>>) -> crate::Result<()> {
+    let invalid = invalid_urls(config);
+    if invalid.is_empty() {
+        return Ok(());
+    }
+
+    let lines: Vec<String> = invalid.iter().map(|url| format!("  {}", url)).collect();
+    Err(anyhow::anyhow!(
+        "{} invalid URL(s) in the catalog:\n{}",
+        invalid.len(),
+        lines.join("\n")
+    )
+    .into())
+}
+
+/// Reject a database declared twice, or a genome version declared twice
+/// under one database. Deserializing into a map would otherwise keep the last
+/// definition and silently drop the first.
+fn check_duplicate_keys(yaml: &str) -> crate::Result<()> {
+    // Line of each database, and of each version within the current one.
+    let mut databases: HashMap<String, usize> = HashMap::new();
+    let mut versions: HashMap<String, usize> = HashMap::new();
+    let mut current_db = String::new();
+    let mut version_indent = None;
+
+    for (index, line) in yaml.lines().enumerate() {
+        let line_number = index + 1;
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("---") {
+            continue;
+        }
+
+        let indent = line.len() - trimmed.len();
+        let Some((key, _)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let key = key
+            .trim()
+            .trim_matches(|c| c == '"' || c == '\'')
+            .to_string();
+
+        if indent == 0 {
+            if let Some(first) = databases.insert(key.clone(), line_number) {
+                return Err(anyhow::anyhow!(
+                    "Database '{}' is defined twice (lines {} and {})",
+                    key,
+                    first,
+                    line_number
+                )
+                .into());
+            }
+            current_db = key;
+            versions.clear();
+            version_indent = None;
+        } else if *version_indent.get_or_insert(indent) == indent {
+            if let Some(first) = versions.insert(key.clone(), line_number) {
+                return Err(anyhow::anyhow!(
+                    "Genome version '{}' of database '{}' is defined twice (lines {} and {})",
+                    key,
+                    current_db,
+                    first,
+                    line_number
+                )
+                .into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_indexes(config: &HashMap<String, HashMap<String, DatabaseFiles>>) -> crate::Result<()> {
+    for (db_name, versions) in config {
+        for (genome_version, files) in versions {
+            if files.tbi.is_none() && files.csi.is_none() {
+                return Err(anyhow::anyhow!(
+                    "{}/{} needs a tbi or csi index URL",
+                    db_name,
+                    genome_version
+                )
+                .into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The `Content-MD5` header and S3 ETags only ever carry MD5.
+fn validate_checksums(
+    config: &HashMap<String, HashMap<String, DatabaseFiles>>,
+) -> crate::Result<()> {
+    for (db_name, versions) in config {
+        for (genome_version, files) in versions {
+            if !files.checksum_algo.is_md5()
+                && matches!(
+                    files.checksum_source,
+                    ChecksumSource::ContentMd5 | ChecksumSource::Etag
+                )
+            {
+                return Err(anyhow::anyhow!(
+                    "{}/{} takes its checksum from a header that only carries MD5, but checksum_algo is {}",
+                    db_name,
+                    genome_version,
+                    files.checksum_algo
+                )
+                .into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_short_names(
+    config: &HashMap<String, HashMap<String, DatabaseFiles>>,
+) -> crate::Result<()> {
+    let mut seen: HashMap<&str, (&str, &str)> = HashMap::new();
+
+    for (db_name, versions) in config {
+        for (genome_version, files) in versions {
+            let Some(short_name) = files.short_name.as_deref() else {
+                continue;
+            };
+
+            if let Some((other_db, other_version)) =
+                seen.insert(short_name, (db_name, genome_version))
+            {
+                return Err(anyhow::anyhow!(
+                    "Short name '{}' is used by both {}/{} and {}/{}",
+                    short_name,
+                    other_db,
+                    other_version,
+                    db_name,
+                    genome_version
+                )
+                .into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The catalog entry for a database and genome version, or the matching
+/// not-found error.
+pub fn lookup<'a>(
+    config: &'a HashMap<String, HashMap<String, DatabaseFiles>>,
+    db_name: &str,
+    genome_version: &str,
+) -> crate::Result<&'a DatabaseFiles> {
+    config
+        .get(db_name)
+        .ok_or_else(|| crate::Error::DatabaseNotFound(db_name.to_string()))?
+        .get(genome_version)
+        .ok_or_else(|| crate::Error::GenomeVersionNotFound {
+            database: db_name.to_string(),
+            version: genome_version.to_string(),
+        })
+}
+
+/// Resolve a database and genome version given on the command line, where
+/// `name` may be a short name standing for both.
+pub fn resolve_database(
+    config: &HashMap<String, HashMap<String, DatabaseFiles>>,
+    name: &str,
+    genome_version: Option<&str>,
+) -> crate::Result<(String, String)> {
+    let by_short_name = config.iter().find_map(|(db_name, versions)| {
+        versions.iter().find_map(|(version, files)| {
+            (files.short_name.as_deref() == Some(name)).then(|| (db_name.clone(), version.clone()))
+        })
+    });
+
+    match (by_short_name, genome_version) {
+        (Some((db_name, version)), None) => Ok((db_name, version)),
+        (Some((db_name, version)), Some(requested)) if requested == version => {
+            Ok((db_name, version))
+        }
+        (Some((db_name, version)), Some(requested)) => Err(anyhow::anyhow!(
+            "Short name '{}' refers to {}/{}, not genome version '{}'",
+            name,
+            db_name,
+            version,
+            requested
+        )
+        .into()),
+        (None, Some(version)) => Ok((name.to_string(), version.to_string())),
+        (None, None) => Err(anyhow::anyhow!(
+            "'{}' is not a short name; specify --genome-version as well",
+            name
+        )
+        .into()),
+    }
+}
+
+/// The catalog with every database and version resolved to its final values,
+/// ordered by name so the serialized form is stable across runs.
+#[derive(Debug, Serialize)]
+pub struct CatalogDump {
+    pub databases: BTreeMap<String, BTreeMap<String, DatabaseFilesDump>>,
+}
+
+/// A version's entry as dumped: unlike [`DatabaseFiles`], every field is
+/// written, defaults included, so the dump shows what is in effect.
+#[derive(Debug, Serialize)]
+pub struct DatabaseFilesDump {
+    pub short_name: Option<String>,
+    pub vcf: String,
+    pub vcf_mirrors: Vec<String>,
+    pub tbi: Option<String>,
+    pub csi: Option<String>,
+    pub md5: String,
+    pub headers: BTreeMap<String, String>,
+    pub host_limits: Option<HostLimits>,
+    pub timeout: Option<u64>,
+    pub retries: Option<u32>,
+    pub promote_delay: Option<u64>,
+    pub supports_head: bool,
+    pub http1_only: bool,
+    pub checksum_source: ChecksumSource,
+    pub checksum_target: ChecksumTarget,
+    pub checksum_algo: HashAlgorithm,
+}
+
+impl From<DatabaseFiles> for DatabaseFilesDump {
+    fn from(files: DatabaseFiles) -> Self {
+        Self {
+            short_name: files.short_name,
+            vcf: files.vcf,
+            vcf_mirrors: files.vcf_mirrors,
+            tbi: files.tbi,
+            csi: files.csi,
+            md5: files.md5,
+            headers: files.headers.into_iter().collect(),
+            host_limits: files.host_limits,
+            timeout: files.timeout,
+            retries: files.retries,
+            promote_delay: files.promote_delay,
+            supports_head: files.supports_head,
+            http1_only: files.http1_only,
+            checksum_source: files.checksum_source,
+            checksum_target: files.checksum_target,
+            checksum_algo: files.checksum_algo,
+        }
+    }
+}
+
+pub fn dump_catalog(explicit: Option<&Path>) -> crate::Result<CatalogDump> {
+    let databases = load_config_from(explicit)?
+        .into_iter()
+        .map(|(db_name, versions)| {
+            let versions = versions
+                .into_iter()
+                .map(|(version, files)| (version, files.into()))
+                .collect();
+            (db_name, versions)
+        })
+        .collect();
+
+    Ok(CatalogDump { databases })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A one-database catalog whose GRCh38 entry ends with `extra` fields.
+    fn catalog(extra: &str) -> String {
+        format!(
+            "clinvar:\n  GRCh38:\n    vcf: https://example.org/clinvar.vcf.gz\n    tbi: https://example.org/clinvar.vcf.gz.tbi\n    md5: https://example.org/clinvar.vcf.gz.md5\n{}",
+            extra
+        )
+    }
+
+    fn files(config: &HashMap<String, HashMap<String, DatabaseFiles>>) -> &DatabaseFiles {
+        lookup(config, "clinvar", "GRCh38").unwrap()
+    }
+
+    #[test]
+    fn request_options_apply_per_database_overrides() {
+        let config = parse_config(&catalog("    timeout: 30\n    retries: 7\n")).unwrap();
+
+        let options = files(&config).request_options().unwrap();
+
+        assert_eq!(options.timeout, Some(Duration::from_secs(30)));
+        assert_eq!(options.retries, Some(7));
+    }
+
+    #[test]
+    fn request_options_default_to_the_downloader_settings() {
+        let config = parse_config(&catalog("")).unwrap();
+
+        let options = files(&config).request_options().unwrap();
+
+        assert_eq!(options.timeout, None);
+        assert_eq!(options.retries, None);
+    }
+
+    #[test]
+    fn rejects_unparseable_timeout_and_retries() {
+        assert!(parse_config(&catalog("    timeout: soon\n")).is_err());
+        assert!(parse_config(&catalog("    retries: -1\n")).is_err());
+    }
+
+    #[test]
+    fn rejects_zero_timeout() {
+        let config = parse_config(&catalog("    timeout: 0\n")).unwrap();
+
+        assert!(files(&config).request_options().is_err());
+    }
+
+    #[test]
+    fn explicit_config_path_wins() {
+        let path = Path::new("/etc/glade/catalog.yaml");
+
+        assert_eq!(config_path_from(Some(path)), Some(path.to_path_buf()));
+    }
+
+    #[test]
+    fn explicit_config_is_merged_over_the_built_in_catalog() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("catalog.yaml");
+        std::fs::write(&path, catalog("")).unwrap();
+
+        let config = load_config_from(Some(&path)).unwrap();
+
+        let clinvar = &config["clinvar"];
+        assert_eq!(clinvar["GRCh38"].vcf, "https://example.org/clinvar.vcf.gz");
+        assert!(clinvar.contains_key("GRCh37"), "built-in versions are kept");
+    }
+
+    #[test]
+    fn duplicate_database_key_is_an_error() {
+        let yaml = format!("{}{}", catalog(""), catalog(""));
+
+        let error = parse_config(&yaml).unwrap_err();
+
+        assert!(
+            error
+                .to_string()
+                .contains("Database 'clinvar' is defined twice (lines 1 and 6)"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn duplicate_genome_version_key_is_an_error() {
+        let yaml = format!(
+            "{}  GRCh38:\n    vcf: https://example.org/other.vcf.gz\n",
+            catalog("")
+        );
+
+        let error = parse_config(&yaml).unwrap_err();
+
+        assert!(
+            error.to_string().contains(
+                "Genome version 'GRCh38' of database 'clinvar' is defined twice (lines 2 and 6)"
+            ),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn duplicate_key_in_user_catalog_is_an_error() {
+        let user = format!("{}{}", catalog(""), catalog(""));
+
+        assert!(merge_configs(&catalog(""), &user).is_err());
+    }
+
+    #[test]
+    fn user_field_overrides_only_that_field() {
+        let user = "clinvar:\n  GRCh38:\n    retries: 7\n";
+
+        let config = merge_configs(&catalog("    timeout: 30\n"), user).unwrap();
+
+        let files = files(&config);
+        assert_eq!(files.retries, Some(7));
+        assert_eq!(files.timeout, Some(30));
+        assert_eq!(files.vcf, "https://example.org/clinvar.vcf.gz");
+        assert_eq!(
+            files.tbi.as_deref(),
+            Some("https://example.org/clinvar.vcf.gz.tbi")
+        );
+        assert_eq!(files.md5, "https://example.org/clinvar.vcf.gz.md5");
+    }
+
+    #[test]
+    fn user_database_is_added_beside_the_built_in_ones() {
+        let user = catalog("").replacen("clinvar", "other", 1);
+
+        let config = merge_configs(&catalog(""), &user).unwrap();
+
+        assert_eq!(config.len(), 2);
+        assert!(config["clinvar"].contains_key("GRCh38"));
+        assert!(config["other"].contains_key("GRCh38"));
+    }
+
+    #[test]
+    fn same_version_under_different_databases_is_fine() {
+        let yaml = format!(
+            "{}{}",
+            catalog(""),
+            catalog("").replacen("clinvar", "other", 1)
+        );
+
+        assert_eq!(parse_config(&yaml).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn dump_shows_every_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("catalog.yaml");
+        std::fs::write(&path, catalog("")).unwrap();
+
+        let dump = serde_yaml::to_string(&dump_catalog(Some(&path)).unwrap()).unwrap();
+
+        for line in [
+            "supports_head: true",
+            "http1_only: false",
+            "checksum_source: sidecar",
+            "checksum_target: compressed",
+            "checksum_algo: md5",
+            "timeout: null",
+            "retries: null",
+            "promote_delay: null",
+        ] {
+            assert!(dump.contains(line), "missing {:?} in\n{}", line, dump);
+        }
+    }
+
+    #[test]
+    fn user_yaml_skips_defaults() {
+        let config = parse_config(&catalog("")).unwrap();
+
+        let yaml = serde_yaml::to_string(files(&config)).unwrap();
+
+        assert!(!yaml.contains("supports_head"), "{}", yaml);
+        assert!(!yaml.contains("checksum_algo"), "{}", yaml);
+    }
 }
@@ -1,200 +1,3549 @@
 use anyhow::Context;
+use chrono::Utc;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
 
-use crate::config::load_config;
-use crate::downloader::{create_symlink, parse_md5_file, verify_md5, Downloader};
-use crate::Result;
+use crate::attestation::{write_attestation, SigningKey, ATTESTATION_FILE};
+use crate::budget::ByteBudget;
+use crate::checksum::{ChecksumMethod, ChecksumTarget, HashAlgorithm};
+use crate::config::{glade_home, load_config_from, lookup, resolve_database, DatabaseFiles};
+use crate::downloader::{
+    calculate_checksum_cancellable, calculate_checksum_decompressed,
+    calculate_checksum_with_progress, calculate_md5, fallback_release_date, is_link,
+    parse_checksum_file, parse_release_date, part_path, read_link, remove_partial, update_symlink,
+    validate_bgzip, Downloader, ProgressCallback, RequestOptions, RetryPolicy,
+};
+use crate::events::{EventSender, GladeEvent};
+use crate::journal::DownloadJournal;
+use crate::report::{QuietReporter, Reporter, StderrReporter, StdoutReporter};
+use crate::vcf::{self, OutOfOrder};
+use crate::{Error, Result};
+
+/// Options controlling how a single database download behaves.
+#[derive(Debug, Clone, Default)]
+pub struct DownloadOptions {
+    /// Keep only the VCF. The MD5 file is still fetched to verify the VCF, but
+    /// neither it nor the index is written to disk.
+    pub vcf_only: bool,
+    /// Verify the VCF against this checksum, of the given algorithm, instead
+    /// of the mirror's published one. The MD5 file is then neither downloaded
+    /// nor stored.
+    pub expected_checksum: Option<(String, HashAlgorithm)>,
+    /// With `--all`, download only this genome version of each database;
+    /// databases without it are reported as skipped.
+    pub genome_version_filter: Option<String>,
+    /// Re-download a database whose current local release is older than this,
+    /// even if its files are present and valid.
+    pub max_age: Option<Duration>,
+    /// Store file contents once under `<base_dir>/objects/<md5>` and hard-link
+    /// them into dated directories, so identical files across releases and
+    /// genome versions share disk space.
+    pub content_addressed: bool,
+    /// Fail instead of downloading unverified when the mirror's checksum is
+    /// missing, empty or malformed.
+    pub require_checksum: bool,
+    /// Hold a newly downloaded release back from the stable symlinks for this
+    /// long, overriding the catalog's `promote_delay`.
+    pub promote_delay: Option<Duration>,
+    /// How the release directory is named.
+    pub layout: Layout,
+    /// Check that each index's sequence names match the VCF's declared
+    /// contigs, catching a valid index for a different file.
+    pub verify_index_match: bool,
+    /// Download every file again even if it's already present and valid. A
+    /// re-downloaded file only replaces the existing one once it verifies.
+    pub force: bool,
+    /// Resolve the release and print which files would be downloaded and
+    /// where, without downloading anything but the checksum file.
+    pub dry_run: bool,
+    /// Sign an attestation of each verified release with this key, written
+    /// beside its manifest.
+    pub signing_key: Option<Arc<SigningKey>>,
+}
+
+/// How release directories under `<db>/<version>` are named.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Layout {
+    /// `<date>/`, the release date.
+    #[default]
+    Date,
+    /// `md5-<prefix>/`, the first digits of the VCF's MD5, with `<date>` a
+    /// symlink to it. Identical content shares a directory and a re-published
+    /// release under the same date gets its own.
+    Checksum,
+}
+
+/// Digits of the MD5 used to name a checksum-layout directory.
+const CHECKSUM_PREFIX_LEN: usize = 12;
+
+/// Persisted bookkeeping for `--since-last-run`, stored in the base directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RunState {
+    pub last_run: Option<String>,
+    /// Release date last seen for each `database/genome_version`.
+    #[serde(default)]
+    pub releases: BTreeMap<String, String>,
+    /// Verified releases waiting out their promote delay, by
+    /// `database/genome_version`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pending_promotions: BTreeMap<String, PendingPromotion>,
+}
+
+/// A release that's fully downloaded but not yet linked as current.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingPromotion {
+    pub release: String,
+    /// RFC 3339 time after which the next run promotes it.
+    pub promote_after: String,
+}
+
+/// A database whose remote release changed since the previous run.
+#[derive(Debug, Clone)]
+pub struct ReleaseChange {
+    pub db_name: String,
+    pub genome_version: String,
+    pub previous: Option<String>,
+    pub current: String,
+}
+
+/// What `repair_database` did with each managed file.
+#[derive(Debug, Clone, Default)]
+pub struct RepairReport {
+    pub repaired: Vec<String>,
+    pub intact: Vec<String>,
+    /// Broken files that couldn't be fetched again because the mirror has
+    /// moved on to another release.
+    pub unrepaired: Vec<String>,
+}
+
+/// What `prune` removed from and kept of a database's releases.
+#[derive(Debug, Clone, Default)]
+pub struct PruneReport {
+    /// Releases deleted, oldest first.
+    pub removed: Vec<String>,
+    /// Releases kept, oldest first.
+    pub kept: Vec<String>,
+    pub freed: u64,
+}
+
+/// A directory under the data directory that no catalog entry accounts for.
+#[derive(Debug, Clone)]
+pub struct Orphan {
+    pub path: PathBuf,
+    pub database: String,
+    /// `None` when the whole database has left the catalog.
+    pub genome_version: Option<String>,
+    pub size: u64,
+    /// Whether the directory holds glade downloads. Anything else, such as a
+    /// directory a user created by hand, is reported but never deleted.
+    pub recognised: bool,
+}
+
+/// Outcome of checking one file in `verify_database_with_progress`.
+#[derive(Debug, Clone)]
+pub struct FileVerification {
+    pub file: String,
+    pub passed: bool,
+    /// What's wrong when it didn't pass.
+    pub problem: Option<String>,
+    /// The expected and actual checksums, when the VCF didn't match.
+    pub checksum_mismatch: Option<(String, String)>,
+}
+
+/// Offline verdict on a downloaded VCF, from `verify_database`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum VerifyStatus {
+    Valid,
+    /// The VCF, or the checksum sidecar it's checked against, isn't there.
+    Missing {
+        file: String,
+    },
+    ChecksumMismatch {
+        expected: String,
+        actual: String,
+    },
+}
+
+/// Whether the mirror has a newer release than the local one, from
+/// `check_update`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum UpdateStatus {
+    UpToDate { current: String },
+    UpdateAvailable { current: String, latest: String },
+    NotDownloaded { latest: String },
+}
+
+/// A catalog entry and where it is on disk, from `databases_status`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseStatus {
+    pub database: String,
+    pub genome_version: String,
+    pub short_name: Option<String>,
+    pub vcf_url: String,
+    pub tbi_url: Option<String>,
+    pub csi_url: Option<String>,
+    pub md5_url: String,
+    /// Whether the VCF symlink resolves to a downloaded file.
+    pub downloaded: bool,
+    /// Directory holding the symlinks and dated releases.
+    pub directory: PathBuf,
+    /// The VCF the symlink resolves to.
+    pub vcf_path: Option<PathBuf>,
+    /// Name of the release directory the VCF is in.
+    pub release: Option<String>,
+}
+
+/// Remote state of one catalog entry, as found by `survey`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SurveyEntry {
+    pub database: String,
+    pub genome_version: String,
+    pub vcf_url: String,
+    /// Whether the VCF answered a HEAD (or ranged GET) successfully.
+    pub available: bool,
+    /// Why the VCF isn't available.
+    pub error: Option<String>,
+    pub size: Option<u64>,
+    /// Release date from the checksum filename or the VCF's Last-Modified.
+    pub remote_release: Option<String>,
+    pub checksum: Option<String>,
+    pub checksum_algo: HashAlgorithm,
+    /// Why the published checksum couldn't be read.
+    pub checksum_error: Option<String>,
+    /// Release the local symlinks point at, if downloaded.
+    pub local_release: Option<String>,
+    pub status: ReleaseStatus,
+}
+
+/// How the local copy of a database compares with its mirror.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReleaseStatus {
+    NotDownloaded,
+    UpToDate,
+    UpdateAvailable,
+    /// The local release is newer than what the mirror now publishes.
+    LocalNewer,
+    /// The mirror didn't say enough to compare.
+    Unknown,
+}
+
+impl std::fmt::Display for ReleaseStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ReleaseStatus::NotDownloaded => "not downloaded",
+            ReleaseStatus::UpToDate => "up to date",
+            ReleaseStatus::UpdateAvailable => "update available",
+            ReleaseStatus::LocalNewer => "local copy is newer",
+            ReleaseStatus::Unknown => "unknown",
+        })
+    }
+}
+
+const STATE_FILE: &str = ".state.json";
+/// Databases downloaded at once by default with `--all`.
+pub const DEFAULT_JOBS: usize = 3;
+const OBJECTS_DIR: &str = "objects";
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Provenance of a downloaded release, kept as `manifest.json` in its
+/// release directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub database: String,
+    pub genome_version: String,
+    /// The release date parsed from the mirror, which names the directory.
+    pub release: String,
+    /// When the download finished, in RFC 3339.
+    pub downloaded_at: String,
+    pub checksum_algorithm: Option<HashAlgorithm>,
+    /// The VCF checksum published by the mirror or supplied by the user.
+    pub expected_checksum: Option<String>,
+    /// Whether the VCF was hashed and matched `expected_checksum`.
+    pub checksum_verified: bool,
+    pub files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestFile {
+    pub name: String,
+    /// Where the file was downloaded from.
+    pub url: String,
+    pub size: u64,
+}
+
+/// A file fetched by `download_and_verify`.
+struct Fetched {
+    url: String,
+    /// Whether it was hashed and matched the expected checksum.
+    verified: bool,
+}
+
+/// A database whose files are on disk but whose VCF checksum may still be
+/// computing on a blocking thread. The release's symlinks and the completion
+/// report wait for it so nothing ever points at an unverified file.
+struct PendingDownload {
+    db_name: String,
+    genome_version: String,
+    date: String,
+    db_dir: PathBuf,
+    check: Option<PendingCheck>,
+    content_addressed: bool,
+    /// How long a new release waits before its symlinks are repointed, if
+    /// it waits at all.
+    promote_delay: Option<Duration>,
+    verify_index_match: bool,
+    /// Each file of the release and the URL it came from, for the manifest.
+    sources: Vec<(String, String)>,
+    checksum: Option<(String, HashAlgorithm)>,
+    vcf_verified: bool,
+    signing_key: Option<Arc<SigningKey>>,
+}
+
+struct PendingCheck {
+    target_path: PathBuf,
+    symlink_path: PathBuf,
+    expected_checksum: String,
+    task: JoinHandle<Result<()>>,
+    /// The companion files' targets, symlinks and descriptions, linked only
+    /// once the VCF verifies so a bad release never half-replaces a good one.
+    companions: Vec<(PathBuf, PathBuf, String)>,
+}
 
 pub struct DatabaseManager {
     base_dir: PathBuf,
     downloader: Downloader,
+    events: EventSender,
+    hash_permits: Arc<Semaphore>,
+    reporter: Arc<dyn Reporter>,
+    /// Databases downloaded at once by `download_all_databases`.
+    jobs: usize,
+    /// Catalog file to merge over the built-in one instead of the default.
+    config_path: Option<PathBuf>,
+    /// Write each VCF to stdout as it downloads; see
+    /// [`with_vcf_to_stdout`](Self::with_vcf_to_stdout).
+    vcf_to_stdout: bool,
 }
 
 impl DatabaseManager {
+    /// Headers sent with every request, e.g. from `--header` on the CLI.
+    /// Per-database headers from the config take precedence.
+    pub fn with_headers(mut self, headers: &HashMap<String, String>) -> Result<Self> {
+        self.downloader = self.downloader.with_headers(headers)?;
+        Ok(self)
+    }
+
+    /// Fetch each file as this many concurrent byte ranges where the mirror
+    /// allows it, e.g. from `--parallel-chunks`.
+    pub fn with_parallel_chunks(mut self, chunks: u32) -> Self {
+        self.downloader = self.downloader.with_parallel_chunks(chunks);
+        self
+    }
+
+    /// Keep databases under `base_dir` instead, e.g. from `--base-dir`.
+    pub fn with_base_dir(mut self, base_dir: impl Into<PathBuf>) -> Result<Self> {
+        let base_dir = base_dir.into();
+        create_base_dir(&base_dir)?;
+        self.base_dir = base_dir;
+        Ok(self)
+    }
+
+    /// Download up to this many databases at once with `--all`, e.g. from
+    /// `--jobs`.
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.downloader = self.downloader.with_retry_policy(retry);
+        self
+    }
+
+    /// Cap download throughput in bytes per second, e.g. from `--limit-rate`.
+    pub fn with_rate_limit(mut self, bytes_per_sec: u64) -> Self {
+        self.downloader = self.downloader.with_rate_limit(bytes_per_sec);
+        self
+    }
+
+    /// Send every request through a proxy, e.g. from `--proxy`.
+    pub fn with_proxy(mut self, url: &str) -> Result<Self> {
+        self.downloader = self.downloader.with_proxy(url)?;
+        Ok(self)
+    }
+
+    /// Send status output to `reporter` instead of stdout.
+    pub fn with_reporter(mut self, reporter: Arc<dyn Reporter>) -> Self {
+        self.reporter = reporter;
+        self
+    }
+
+    /// Print nothing but errors, e.g. from `--quiet`: no banners, checkmarks
+    /// or progress bars.
+    pub fn with_quiet(mut self) -> Self {
+        self.reporter = Arc::new(QuietReporter);
+        self.downloader = self.downloader.with_quiet();
+        self
+    }
+
+    /// Report file download progress to `callback` instead of a progress bar.
+    pub fn with_progress_callback(mut self, callback: ProgressCallback) -> Self {
+        self.downloader = self.downloader.with_progress_callback(callback);
+        self
+    }
+
+    /// Set or clear the progress callback of an existing manager, e.g. for a
+    /// single download made through the C API.
+    pub fn set_progress_callback(&mut self, callback: Option<ProgressCallback>) {
+        self.downloader.set_progress_callback(callback);
+    }
+
+    /// Pin `host` to `ip`, e.g. from `--resolve`.
+    pub fn with_resolve(mut self, host: &str, ip: std::net::IpAddr) -> Result<Self> {
+        self.downloader = self.downloader.with_resolve(host, ip)?;
+        Ok(self)
+    }
+
+    /// Force HTTP/1.1 for every download, e.g. from `--http1-only`.
+    pub fn with_http1_only(mut self) -> Result<Self> {
+        self.downloader = self.downloader.with_http1_only()?;
+        Ok(self)
+    }
+
+    /// Cap the bytes downloaded by this manager across every file and
+    /// database, e.g. from `--max-total-bytes`.
+    pub fn with_max_total_bytes(mut self, limit: u64) -> Self {
+        self.downloader = self.downloader.with_byte_budget(limit);
+        self
+    }
+
+    pub fn download_budget(&self) -> Option<&ByteBudget> {
+        self.downloader.budget()
+    }
+
+    fn budget_exhausted(&self) -> bool {
+        self.download_budget().is_some_and(ByteBudget::is_exhausted)
+    }
+
+    /// List databases left out because the download budget ran out, as an
+    /// error so the run doesn't look complete.
+    fn report_skipped(&self, skipped: &[String]) -> Result<()> {
+        if skipped.is_empty() {
+            return Ok(());
+        }
+
+        self.reporter.line(format_args!(
+            "\nSkipped because the download budget ran out:"
+        ));
+        for key in skipped {
+            self.reporter.line(format_args!("  {}", key));
+        }
+
+        Err(anyhow::anyhow!(
+            "Download budget exhausted; {} database(s) not downloaded",
+            skipped.len()
+        )
+        .into())
+    }
+
+    /// Read the catalog from `path` (merged over the built-in one) instead
+    /// of `GLADE_CONFIG` or `~/.glade/databases.yaml`, e.g. from `--config`.
+    pub fn with_config_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_path = Some(path.into());
+        self
+    }
+
+    /// Stream each VCF to stdout as it downloads, so a consumer can start
+    /// on it at once, while still saving and verifying it as usual. A VCF
+    /// that's already present is copied out once checked. Status lines move
+    /// to stderr and progress bars are off; mirrors aren't tried, since a
+    /// download that fails partway can't be replayed from another one. The
+    /// output is only known to be intact if the download succeeds.
+    pub fn with_vcf_to_stdout(mut self) -> Self {
+        self.vcf_to_stdout = true;
+        self.reporter = Arc::new(StderrReporter);
+        self.downloader = self.downloader.with_quiet();
+        self
+    }
+
+    /// The catalog this manager works from.
+    pub fn load_config(&self) -> Result<HashMap<String, HashMap<String, DatabaseFiles>>> {
+        load_config_from(self.config_path.as_deref())
+    }
+
+    /// A manager keeping databases under `databases` in [`glade_home`]:
+    /// `$GLADE_HOME/databases`, or `~/.glade/databases` by default.
     pub fn new() -> Result<Self> {
-        let base_dir = dirs::home_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))?
-            .join(".glade")
+        let base_dir = glade_home()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine home directory{}", home_hint()))?
             .join("databases");
+        Self::new_in(base_dir)
+    }
+
+    /// A manager keeping databases under `base_dir`, creating it if needed.
+    pub fn new_in(base_dir: impl Into<PathBuf>) -> Result<Self> {
+        let base_dir = base_dir.into();
+        create_base_dir(&base_dir)?;
 
-        fs::create_dir_all(&base_dir).context("Failed to create base directory")?;
+        let events = EventSender::default();
+
+        let hash_workers = std::thread::available_parallelism().map_or(1, |n| n.get());
 
         Ok(Self {
             base_dir,
-            downloader: Downloader::new()?,
+            downloader: Downloader::new()?.with_events(events.clone()),
+            events,
+            hash_permits: Arc::new(Semaphore::new(hash_workers)),
+            reporter: Arc::new(StdoutReporter),
+            jobs: DEFAULT_JOBS,
+            config_path: None,
+            vcf_to_stdout: false,
         })
     }
 
     pub async fn download_database(&self, db_name: &str, genome_version: &str) -> Result<()> {
-        let config = load_config()?;
+        self.download_database_with_options(db_name, genome_version, &DownloadOptions::default())
+            .await
+    }
 
-        let db_config = config
-            .get(db_name)
-            .ok_or_else(|| anyhow::anyhow!("Database '{}' not found in configuration", db_name))?;
+    /// Subscribe to lifecycle events for every download made through this
+    /// manager. Slow receivers may observe `RecvError::Lagged` and skip ahead.
+    pub fn events(&self) -> tokio::sync::broadcast::Receiver<GladeEvent> {
+        self.events.subscribe()
+    }
 
-        let version_config = db_config.get(genome_version).ok_or_else(|| {
-            anyhow::anyhow!(
-                "Genome version '{}' not found for database '{}'",
-                genome_version,
-                db_name
-            )
-        })?;
+    pub async fn download_database_with_options(
+        &self,
+        db_name: &str,
+        genome_version: &str,
+        options: &DownloadOptions,
+    ) -> Result<()> {
+        let pending = self.start_download(db_name, genome_version, options).await;
+        let result = match pending {
+            Ok(Some(pending)) => self.finish_download(pending).await,
+            Ok(None) => Ok(()),
+            Err(e) => Err(e),
+        };
 
-        println!(
-            "Downloading {} database for genome version {}",
-            db_name, genome_version
-        );
-        println!("{}", "=".repeat(60));
+        self.report_failure(db_name, genome_version, &result);
+        result
+    }
 
-        let md5_content = self
-            .downloader
-            .download_text(&version_config.md5)
-            .await
-            .context("Failed to download MD5 file")?;
+    /// Announce the end of a run to event subscribers, with its outcome.
+    pub fn finish_run(&self, result: &Result<()>) {
+        self.events.emit(GladeEvent::RunFinished {
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+    }
+
+    fn report_failure(&self, db_name: &str, genome_version: &str, result: &Result<()>) {
+        if let Err(e) = result {
+            self.events.emit(GladeEvent::Failed {
+                database: db_name.to_string(),
+                genome_version: genome_version.to_string(),
+                error: e.to_string(),
+            });
+        }
+    }
+
+    /// Download every file of a database. A freshly downloaded VCF is hashed
+    /// in the background; pass the result to `finish_download` to wait for it.
+    /// A dry run only prints the plan and returns `None`.
+    async fn start_download(
+        &self,
+        db_name: &str,
+        genome_version: &str,
+        options: &DownloadOptions,
+    ) -> Result<Option<PendingDownload>> {
+        let config = self.load_config()?;
+        let version_config = lookup(&config, db_name, genome_version)?;
+
+        self.apply_host_settings(version_config)?;
+        let request_options = version_config.request_options()?;
+
+        self.reporter.line(format_args!(
+            "{} {} database for genome version {}",
+            if options.dry_run {
+                "Planning"
+            } else {
+                "Downloading"
+            },
+            db_name,
+            genome_version
+        ));
+        self.reporter.line(format_args!("{}", "=".repeat(60)));
 
-        let (expected_md5, date) = parse_md5_file(&md5_content)?;
+        if !options.dry_run {
+            self.promote_if_due(db_name, genome_version)?;
+        }
+
+        let method = ChecksumMethod {
+            algorithm: match &options.expected_checksum {
+                Some((_, algorithm)) => *algorithm,
+                None => version_config.checksum_algo,
+            },
+            target: version_config.checksum_target,
+        };
+        let (expected_checksum, date) = match &options.expected_checksum {
+            Some((hash, _)) => {
+                self.reporter.line(format_args!(
+                    "Using user-supplied {} checksum: {}",
+                    method.algorithm, hash
+                ));
+                (
+                    Some(hash.clone()),
+                    self.release_date_from_vcf(&version_config.vcf, &request_options)
+                        .await,
+                )
+            }
+            None => {
+                self.resolve_release(version_config, options.require_checksum)
+                    .await?
+            }
+        };
 
         let db_dir = self.base_dir.join(db_name).join(genome_version);
         let dated_dir = db_dir.join(&date);
+        let mut files = vec![("VCF", version_config.vcf.as_str(), "clinvar.vcf.gz")];
+        files.extend(version_config.indexes());
+        files.push(("MD5", version_config.md5.as_str(), "clinvar.vcf.gz.md5"));
+
+        if options.expected_checksum.is_some()
+            || expected_checksum.is_none()
+            || !version_config.checksum_source.is_sidecar()
+        {
+            files.retain(|(desc, _, _)| *desc != "MD5");
+        }
+
+        if options.dry_run {
+            let count = if options.vcf_only { 1 } else { files.len() };
+            self.print_plan(&dated_dir, &files[..count], version_config);
+            return Ok(None);
+        }
+
+        match (options.layout, &expected_checksum) {
+            (Layout::Checksum, Some(md5)) if !dated_dir.is_dir() || dated_dir.is_symlink() => {
+                link_checksum_dir(&*self.reporter, &db_dir, &date, md5)?;
+            }
+            (Layout::Checksum, Some(_)) => self.reporter.line(format_args!(
+                "  ⚠ {} is already a date-named directory; keeping the date layout",
+                dated_dir.display()
+            )),
+            (Layout::Checksum, None) => self.reporter.line(format_args!(
+                "  ⚠ No checksum to name the directory by; using the date layout"
+            )),
+            (Layout::Date, _) => {}
+        }
         fs::create_dir_all(&dated_dir).context("Failed to create database directory")?;
 
-        let files = vec![
-            ("VCF", &version_config.vcf, "clinvar.vcf.gz"),
-            ("TBI", &version_config.tbi, "clinvar.vcf.gz.tbi"),
-            ("MD5", &version_config.md5, "clinvar.vcf.gz.md5"),
-        ];
+        // Only a release that isn't already current has to settle first.
+        let promote_delay = options
+            .promote_delay
+            .or(version_config.promote_delay.map(Duration::from_secs))
+            .filter(|delay| !delay.is_zero())
+            .filter(|_| self.current_release(db_name, genome_version).as_ref() != Some(&date));
+
+        let stale_release = options.max_age.and_then(|max_age| {
+            self.current_release(db_name, genome_version)
+                .filter(|release| release_age(release).is_some_and(|age| age > max_age))
+        });
+        if let Some(release) = &stale_release {
+            self.reporter.line(format_args!(
+                "  ↻ Local release {} is older than the maximum age; refreshing",
+                release
+            ));
+            if !is_newer_release(&date, release) {
+                self.reporter.line(format_args!(
+                    "  ⚠ Remote release {} is no newer than the local copy",
+                    date
+                ));
+            }
+        }
+
+        if options.vcf_only {
+            // Drop companion symlinks from an earlier full download so they
+            // can't be mistaken for files matching this VCF.
+            for (_, _, filename) in files.drain(1..) {
+                let symlink_path = db_dir.join(filename);
+                if is_link(&symlink_path) && read_link(&symlink_path)? != dated_dir.join(filename) {
+                    fs::remove_file(&symlink_path)?;
+                }
+            }
+        }
+
+        let mut check = None;
+        let mut sources = Vec::new();
+        let mut vcf_verified = false;
+        let previous_manifest = read_manifest_in(&dated_dir).ok();
 
         for (desc, url, filename) in files {
             let target_path = dated_dir.join(filename);
             let symlink_path = db_dir.join(filename);
+            let streaming = self.vcf_to_stdout && filename == "clinvar.vcf.gz";
+            let mut urls = version_config.urls_for(url);
+            if streaming {
+                urls.truncate(1);
+            }
+
+            if stale_release.is_some() {
+                if target_path.exists() {
+                    fs::remove_file(&target_path)?;
+                }
+                remove_partial(&target_path)?;
+            }
 
+            if options.force {
+                // A fresh pull: nothing from an earlier download is reused.
+                remove_partial(&target_path)?;
+                DownloadJournal::remove(&target_path)?;
+            }
+
+            // Downloads are renamed into place once complete, so a journal
+            // left beside an existing file is from a run that died just
+            // after the rename.
             if target_path.exists() {
-                println!("  ✓ {} already exists", desc);
-
-                if filename == "clinvar.vcf.gz" {
-                    print!("    Verifying MD5 checksum... ");
-                    std::io::stdout().flush().unwrap();
-
-                    match verify_md5(&target_path, &expected_md5) {
-                        Ok(true) => println!("✓ Valid"),
-                        Ok(false) => {
-                            println!("✗ Invalid checksum!");
-                            println!("    Expected: {}", expected_md5);
-                            fs::remove_file(&target_path)?;
-                            self.download_and_verify(url, &target_path, desc, Some(&expected_md5))
+                DownloadJournal::remove(&target_path)?;
+            } else if let Some(journal) = DownloadJournal::load(&target_path) {
+                // A previous run died mid-download; its .part file is a prefix
+                // to resume.
+                let expected = expected_checksum
+                    .as_deref()
+                    .filter(|_| filename == "clinvar.vcf.gz");
+                let same_checksum = journal.expected_checksum.is_none()
+                    || journal.expected_checksum.as_deref() == expected;
+                if urls.contains(&journal.url.as_str()) && same_checksum {
+                    self.reporter.line(format_args!(
+                        "  ↻ Resuming interrupted {} download ({} bytes written)",
+                        desc, journal.bytes_written
+                    ));
+                } else {
+                    self.reporter.line(format_args!(
+                        "  ↻ Discarding interrupted {} download of another release",
+                        desc
+                    ));
+                    remove_partial(&target_path)?;
+                    DownloadJournal::remove(&target_path)?;
+                }
+            }
+
+            if target_path.exists() && !options.force {
+                self.reporter
+                    .line(format_args!("  ✓ {} already exists", desc));
+
+                if let (true, Some(expected_checksum)) =
+                    (filename == "clinvar.vcf.gz", &expected_checksum)
+                {
+                    self.reporter.partial(format_args!(
+                        "    Verifying {} checksum... ",
+                        method.algorithm
+                    ));
+
+                    let verified = self
+                        .check_md5(&target_path, expected_checksum, method)
+                        .await;
+                    if let Some(valid) = checksum_outcome(&verified) {
+                        self.emit_verified(db_name, genome_version, filename, valid);
+                    }
+
+                    match verified {
+                        Ok(()) => {
+                            self.reporter.line(format_args!("✓ Valid"));
+                            vcf_verified = true;
+                        }
+                        Err(Error::ChecksumMismatch { .. }) => {
+                            self.reporter.line(format_args!("✗ Invalid checksum!"));
+                            self.reporter
+                                .line(format_args!("    Expected: {}", expected_checksum));
+                            // The file only replaces the bad one once it
+                            // verifies.
+                            let fetched = self
+                                .download_and_verify(
+                                    (db_name, genome_version),
+                                    &urls,
+                                    &target_path,
+                                    desc,
+                                    Some((expected_checksum, method)),
+                                    &request_options,
+                                )
                                 .await?;
+                            vcf_verified = fetched.verified;
+                            sources.push((filename.to_string(), fetched.url));
                         }
                         Err(e) => {
-                            println!("⚠ Could not verify: {}", e);
+                            self.reporter
+                                .line(format_args!("⚠ Could not verify: {}", e));
                         }
                     }
                 }
+
+                if streaming && !sources.iter().any(|(name, _)| name == filename) {
+                    copy_to_stdout(&target_path).await?;
+                }
+
+                if !sources.iter().any(|(name, _)| name == filename) {
+                    // Kept from an earlier run, which recorded its source.
+                    let url = previous_manifest
+                        .as_ref()
+                        .and_then(|manifest| manifest.files.iter().find(|f| f.name == filename))
+                        .map_or(url, |file| file.url.as_str());
+                    sources.push((filename.to_string(), url.to_string()));
+                }
             } else {
-                self.download_and_verify(
-                    url,
-                    &target_path,
-                    desc,
-                    if filename == "clinvar.vcf.gz" {
-                        Some(&expected_md5)
-                    } else {
-                        None
-                    },
-                )
-                .await?;
+                // With mirrors to fall back on, the VCF has to be checked
+                // before moving on rather than in the background. So does one
+                // replacing an existing file the symlink may point at.
+                // So does one streamed to stdout, whose reader relies on the
+                // exit status.
+                let check_now = (urls.len() > 1 || target_path.exists() || streaming)
+                    && filename == "clinvar.vcf.gz";
+                let fetched = self
+                    .download_and_verify(
+                        (db_name, genome_version),
+                        &urls,
+                        &target_path,
+                        desc,
+                        expected_checksum
+                            .as_deref()
+                            .filter(|_| check_now)
+                            .map(|md5| (md5, method)),
+                        &request_options,
+                    )
+                    .await?;
+                if check_now {
+                    vcf_verified = fetched.verified;
+                }
+                sources.push((filename.to_string(), fetched.url));
+
+                if let (false, true, Some(expected_checksum)) =
+                    (check_now, filename == "clinvar.vcf.gz", &expected_checksum)
+                {
+                    // Hash while the remaining files (and, for --all, the next
+                    // database) download; the symlink waits for the result.
+                    self.reporter.line(format_args!(
+                        "    Verifying {} checksum in the background",
+                        method.algorithm
+                    ));
+                    check = Some(PendingCheck {
+                        task: self.spawn_md5_check(&target_path, expected_checksum, method),
+                        target_path,
+                        symlink_path,
+                        expected_checksum: expected_checksum.clone(),
+                        companions: Vec::new(),
+                    });
+                    continue;
+                }
             }
 
-            if !symlink_path.exists() || symlink_path.is_symlink() {
-                create_symlink(&target_path, &symlink_path)
-                    .context(format!("Failed to create symlink for {}", desc))?;
-                println!("    ✓ Updated symlink: {}", symlink_path.display());
+            if promote_delay.is_none() && (!symlink_path.exists() || is_link(&symlink_path)) {
+                match &mut check {
+                    Some(check) => {
+                        check
+                            .companions
+                            .push((target_path, symlink_path, desc.to_string()))
+                    }
+                    None => refresh_symlink(&*self.reporter, &target_path, &symlink_path, desc)?,
+                }
             }
         }
 
-        println!("\n{}", "=".repeat(60));
-        println!("✓ Download complete!");
-        println!("  Database: {}/{}", db_name, genome_version);
-        println!("  Location: {}", db_dir.display());
-        println!("  Date: {}", date);
-        println!("{}", "=".repeat(60));
+        Ok(Some(PendingDownload {
+            db_name: db_name.to_string(),
+            genome_version: genome_version.to_string(),
+            date,
+            db_dir,
+            check,
+            content_addressed: options.content_addressed,
+            promote_delay,
+            verify_index_match: options.verify_index_match,
+            sources,
+            checksum: expected_checksum.map(|md5| (md5, method.algorithm)),
+            vcf_verified,
+            signing_key: options.signing_key.clone(),
+        }))
+    }
 
-        Ok(())
+    /// Print where each of `files` would be downloaded from and to, and
+    /// whether it's already there.
+    fn print_plan(&self, dated_dir: &Path, files: &[(&str, &str, &str)], config: &DatabaseFiles) {
+        self.reporter
+            .line(format_args!("  Release directory: {}", dated_dir.display()));
+        for (desc, url, filename) in files {
+            let target_path = dated_dir.join(filename);
+            if target_path.exists() {
+                self.reporter.line(format_args!(
+                    "  ✓ {} already exists: {}",
+                    desc,
+                    target_path.display()
+                ));
+                continue;
+            }
+            self.reporter
+                .line(format_args!("  ↓ {} would be downloaded", desc));
+            for url in config.urls_for(url) {
+                self.reporter.line(format_args!("    from {}", url));
+            }
+            self.reporter
+                .line(format_args!("    to   {}", target_path.display()));
+        }
+        self.reporter
+            .line(format_args!("  Dry run: nothing was downloaded\n"));
     }
 
-    async fn download_and_verify(
-        &self,
-        url: &str,
-        target_path: &Path,
-        desc: &str,
-        expected_md5: Option<&str>,
-    ) -> Result<()> {
-        println!("  ↓ Downloading {}...", desc);
-        self.downloader
-            .download_file(url, target_path)
-            .await
-            .with_context(|| format!("Failed to download {}", desc))?;
-        println!("    ✓ Download complete");
+    /// Wait for any background checksum, then point the release's symlinks at
+    /// the verified files and report completion.
+    async fn finish_download(&self, pending: PendingDownload) -> Result<()> {
+        let PendingDownload {
+            db_name,
+            genome_version,
+            date,
+            db_dir,
+            check,
+            content_addressed,
+            promote_delay,
+            verify_index_match,
+            sources,
+            checksum,
+            mut vcf_verified,
+            signing_key,
+        } = pending;
 
-        if let Some(md5) = expected_md5 {
-            print!("    Verifying MD5 checksum... ");
-            std::io::stdout().flush().unwrap();
+        if let Some(check) = check {
+            let verified = check
+                .task
+                .await
+                .map_err(|e| anyhow::anyhow!("Checksum task failed: {}", e))?;
+            if let Some(valid) = checksum_outcome(&verified) {
+                self.emit_verified(&db_name, &genome_version, "clinvar.vcf.gz", valid);
+            }
 
-            match verify_md5(target_path, md5) {
-                Ok(true) => println!("✓ Valid"),
-                Ok(false) => {
-                    println!("✗ Invalid checksum!");
-                    fs::remove_file(target_path)?;
-                    return Err(anyhow::anyhow!("Downloaded file has invalid checksum").into());
+            match verified {
+                Ok(()) => {
+                    self.reporter.line(format_args!(
+                        "  ✓ {}/{} VCF checksum valid",
+                        db_name, genome_version
+                    ));
+                    vcf_verified = true;
                 }
-                Err(e) => {
-                    println!("⚠ Could not verify: {}", e);
+                Err(e @ Error::ChecksumMismatch { .. }) => {
+                    self.reporter.line(format_args!(
+                        "  ✗ {}/{} VCF has an invalid checksum!",
+                        db_name, genome_version
+                    ));
+                    self.reporter
+                        .line(format_args!("    Expected: {}", check.expected_checksum));
+                    fs::remove_file(&check.target_path)?;
+                    return Err(e);
+                }
+                Err(e) => self.reporter.line(format_args!(
+                    "  ⚠ Could not verify {}/{}: {}",
+                    db_name, genome_version, e
+                )),
+            }
+
+            if promote_delay.is_none() {
+                refresh_symlink(
+                    &*self.reporter,
+                    &check.target_path,
+                    &check.symlink_path,
+                    "VCF",
+                )?;
+                for (target_path, symlink_path, desc) in &check.companions {
+                    refresh_symlink(&*self.reporter, target_path, symlink_path, desc)?;
                 }
             }
         }
 
+        if verify_index_match {
+            self.verify_index_match(&db_dir.join(&date))?;
+        }
+
+        if let Some(delay) = promote_delay {
+            let promote_after = self.defer_promotion(&db_name, &genome_version, &date, delay)?;
+            self.reporter.line(format_args!(
+                "  ⏸ Release {} is held back until {}; run 'glade database promote' to promote it now",
+                date, promote_after
+            ));
+        }
+
+        if content_addressed {
+            self.store_content_addressed(&db_dir.join(&date)).await?;
+        }
+
+        let release_dir = db_dir.join(&date);
+        let files = sources
+            .into_iter()
+            .map(|(name, url)| {
+                let size = fs::metadata(release_dir.join(&name))?.len();
+                Ok(ManifestFile { name, url, size })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let (expected_checksum, checksum_algorithm) = checksum.unzip();
+        let manifest = Manifest {
+            database: db_name.clone(),
+            genome_version: genome_version.clone(),
+            release: date.clone(),
+            downloaded_at: Utc::now().to_rfc3339(),
+            checksum_algorithm,
+            expected_checksum,
+            checksum_verified: vcf_verified,
+            files,
+        };
+        write_manifest(&release_dir, &manifest)?;
+
+        if let Some(key) = signing_key {
+            if vcf_verified {
+                let dir = release_dir.clone();
+                tokio::task::spawn_blocking(move || write_attestation(&dir, &manifest, &key))
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Attestation task failed: {}", e))??;
+                self.reporter.line(format_args!(
+                    "  ✓ Attestation: {}",
+                    release_dir.join(ATTESTATION_FILE).display()
+                ));
+            } else {
+                self.reporter.line(format_args!(
+                    "  ⚠ Not attesting release {}: its VCF wasn't verified against a checksum",
+                    date
+                ));
+            }
+        }
+
+        self.reporter.line(format_args!("\n{}", "=".repeat(60)));
+        self.reporter.line(format_args!("✓ Download complete!"));
+        self.reporter
+            .line(format_args!("  Database: {}/{}", db_name, genome_version));
+        self.reporter
+            .line(format_args!("  Location: {}", db_dir.display()));
+        self.reporter.line(format_args!("  Date: {}", date));
+        self.reporter.line(format_args!("{}", "=".repeat(60)));
+
+        self.events.emit(GladeEvent::Completed {
+            database: db_name,
+            genome_version,
+            date,
+            path: db_dir.display().to_string(),
+        });
+
         Ok(())
     }
 
-    pub async fn download_all_databases(&self) -> Result<()> {
-        let config = load_config()?;
+    /// Move each regular file in `dir` into the object store and replace it
+    /// with a hard link, reusing an existing object with the same content.
+    async fn store_content_addressed(&self, dir: &Path) -> Result<()> {
+        let objects_dir = self.base_dir.join(OBJECTS_DIR);
+        fs::create_dir_all(&objects_dir).context("Failed to create object store")?;
 
-        for (db_name, versions) in config.iter() {
-            for genome_version in versions.keys() {
-                self.download_database(db_name, genome_version).await?;
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            // The manifest and attestation are rewritten on every download.
+            if path.is_symlink()
+                || !path.is_file()
+                || path.ends_with(MANIFEST_FILE)
+                || path.ends_with(ATTESTATION_FILE)
+            {
+                continue;
+            }
+
+            let hash_path = path.clone();
+            let hash = tokio::task::spawn_blocking(move || calculate_md5(&hash_path))
+                .await
+                .map_err(|e| anyhow::anyhow!("Checksum task failed: {}", e))??;
+            let object = objects_dir.join(&hash);
+
+            if !object.exists() {
+                fs::hard_link(&path, &object).context("Failed to add file to object store")?;
+            } else if !same_file(&path, &object)? {
+                fs::remove_file(&path)?;
+                fs::hard_link(&object, &path).context("Failed to link stored object")?;
+                self.reporter.line(format_args!(
+                    "    ✓ Deduplicated {} (object {})",
+                    path.file_name().unwrap_or_default().to_string_lossy(),
+                    hash
+                ));
             }
         }
 
         Ok(())
     }
 
-    pub fn list_databases(&self) -> Result<()> {
-        let config = load_config()?;
+    /// Compute an MD5 on a blocking thread, bounded by the hashing semaphore
+    /// so large sweeps don't oversubscribe the CPU.
+    fn spawn_md5_check(
+        &self,
+        path: &Path,
+        expected_checksum: &str,
+        method: ChecksumMethod,
+    ) -> JoinHandle<Result<()>> {
+        let permits = self.hash_permits.clone();
+        let path = path.to_path_buf();
+        let expected_checksum = expected_checksum.to_string();
 
-        println!("Available databases:");
-        println!("{}", "=".repeat(60));
+        tokio::spawn(async move {
+            let _permit = permits
+                .acquire_owned()
+                .await
+                .map_err(|e| anyhow::anyhow!("Hashing pool closed: {}", e))?;
+            tokio::task::spawn_blocking(move || method.check(&path, &expected_checksum))
+                .await
+                .map_err(|e| anyhow::anyhow!("Checksum task failed: {}", e))?
+        })
+    }
 
-        for (db_name, versions) in config.iter() {
-            println!("\nDatabase: {}", db_name);
-            for (genome_version, files) in versions.iter() {
-                println!("  Genome Version: {}", genome_version);
-                println!("    VCF: {}", files.vcf);
-                println!("    TBI: {}", files.tbi);
-                println!("    MD5: {}", files.md5);
+    /// Hash `path` on the blocking pool; a mismatch is an
+    /// `Error::ChecksumMismatch`.
+    async fn check_md5(
+        &self,
+        path: &Path,
+        expected_checksum: &str,
+        method: ChecksumMethod,
+    ) -> Result<()> {
+        self.spawn_md5_check(path, expected_checksum, method)
+            .await
+            .map_err(|e| anyhow::anyhow!("Checksum task failed: {}", e))?
+    }
 
-                let db_dir = self.base_dir.join(db_name).join(genome_version);
-                if db_dir.exists() {
-                    println!("    Status: ✓ Downloaded to {}", db_dir.display());
-                } else {
-                    println!("    Status: Not downloaded");
-                }
+    fn emit_verified(&self, db_name: &str, genome_version: &str, file: &str, valid: bool) {
+        self.events.emit(GladeEvent::Verified {
+            database: db_name.to_string(),
+            genome_version: genome_version.to_string(),
+            file: file.to_string(),
+            valid,
+        });
+    }
+
+    /// Fetch the published MD5 from the configured checksum source and work out
+    /// the release date. When the published filename has no date, the VCF's
+    /// `Last-Modified` header is used so the dated directory stays stable
+    /// across runs.
+    ///
+    /// A missing or malformed checksum is fatal when `require_checksum` is set;
+    /// otherwise it is reported and the VCF is downloaded unverified.
+    async fn resolve_release(
+        &self,
+        files: &DatabaseFiles,
+        require_checksum: bool,
+    ) -> Result<(Option<String>, String)> {
+        let request_options = files.request_options()?;
+        let (expected_checksum, date) = match files
+            .checksum_source
+            .fetch(&self.downloader, files, &request_options)
+            .await
+        {
+            Ok((md5, date)) => (Some(md5), date),
+            Err(e) if require_checksum => {
+                return Err(
+                    anyhow::anyhow!("Refusing to proceed without a valid checksum: {}", e).into(),
+                );
+            }
+            Err(e) => {
+                self.reporter.line(format_args!(
+                    "  ⚠ No usable checksum ({}); the VCF will not be verified",
+                    e
+                ));
+                (None, None)
             }
+        };
+        let date = match date {
+            Some(date) => date,
+            None => {
+                self.release_date_from_vcf(&files.vcf, &request_options)
+                    .await
+            }
+        };
+
+        Ok((expected_checksum, date))
+    }
+
+    async fn release_date_from_vcf(&self, vcf_url: &str, options: &RequestOptions) -> String {
+        let last_modified = self
+            .downloader
+            .last_modified(vcf_url, options)
+            .await
+            .unwrap_or(None);
+        fallback_release_date(last_modified.as_deref())
+    }
+
+    /// Check that the current VCF of a downloaded database is coordinate-sorted
+    /// in header contig order, returning the first offending record.
+    pub fn check_sorted(&self, db_name: &str, genome_version: &str) -> Result<Option<OutOfOrder>> {
+        let vcf = self
+            .base_dir
+            .join(db_name)
+            .join(genome_version)
+            .join("clinvar.vcf.gz");
+
+        if !vcf.exists() {
+            return Err(anyhow::anyhow!(
+                "Database {}/{} is not downloaded",
+                db_name,
+                genome_version
+            )
+            .into());
         }
 
-        println!("\n{}", "=".repeat(60));
-        println!("Use 'glade database download --database <NAME> --genome-version <VERSION>' to download");
-        println!("Use 'glade database download --all' to download all databases");
+        crate::vcf::check_sorted(&vcf)
+    }
 
-        Ok(())
+    /// Verify every file of the installed release and re-download only the
+    /// ones that are missing, empty, fail their checksum or don't look like
+    /// what they should be, relinking broken symlinks along the way.
+    pub async fn repair_database(
+        &self,
+        db_name: &str,
+        genome_version: &str,
+    ) -> Result<RepairReport> {
+        let config = self.load_config()?;
+        let version_config = lookup(&config, db_name, genome_version)?;
+
+        let db_dir = self.base_dir.join(db_name).join(genome_version);
+        let release = self
+            .current_release(db_name, genome_version)
+            .or_else(|| latest_release_dir(&db_dir))
+            .ok_or_else(|| {
+                anyhow::anyhow!("Database {}/{} is not downloaded", db_name, genome_version)
+            })?;
+        let dated_dir = db_dir.join(&release);
+
+        self.reporter.line(format_args!(
+            "Repairing {} database for genome version {} (release {})",
+            db_name, genome_version, release
+        ));
+        self.reporter.line(format_args!("{}", "=".repeat(60)));
+
+        self.apply_host_settings(version_config)?;
+        let request_options = version_config.request_options()?;
+        let (expected_checksum, remote_release) =
+            self.resolve_release(version_config, false).await?;
+        // Files fetched now must belong to the installed release, or the
+        // repaired set would mix two releases.
+        let can_fetch = remote_release == release;
+
+        let mut files = vec![("VCF", version_config.vcf.as_str(), "clinvar.vcf.gz")];
+        files.extend(version_config.indexes());
+        if version_config.checksum_source.is_sidecar() {
+            files.push(("MD5", version_config.md5.as_str(), "clinvar.vcf.gz.md5"));
+        }
+
+        let mut report = RepairReport::default();
+
+        for (desc, url, filename) in files {
+            let target_path = dated_dir.join(filename);
+            let symlink_path = db_dir.join(filename);
+            let urls = version_config.urls_for(url);
+
+            // Companion files are optional (e.g. after --vcf-only), so only
+            // the VCF is repaired when it's missing entirely.
+            if !target_path.exists() && desc != "VCF" && !is_link(&symlink_path) {
+                continue;
+            }
+
+            let problem = match self
+                .check_file(
+                    &target_path,
+                    desc,
+                    expected_checksum.as_deref().filter(|_| can_fetch),
+                    version_config.checksum_method(),
+                )
+                .await
+            {
+                Ok(problem) => problem,
+                Err(e) => Some(e.to_string()),
+            };
+
+            if let Some(problem) = problem {
+                self.reporter
+                    .line(format_args!("  ✗ {}: {}", desc, problem));
+                if !can_fetch {
+                    self.reporter.line(format_args!(
+                        "    ⚠ The mirror reports release {}, not {}; run download instead",
+                        remote_release, release
+                    ));
+                    report.unrepaired.push(filename.to_string());
+                    continue;
+                }
+
+                if target_path.exists() {
+                    fs::remove_file(&target_path)?;
+                }
+                let expected = expected_checksum
+                    .as_deref()
+                    .filter(|_| desc == "VCF")
+                    .map(|md5| (md5, version_config.checksum_method()));
+                self.download_and_verify(
+                    (db_name, genome_version),
+                    &urls,
+                    &target_path,
+                    desc,
+                    expected,
+                    &request_options,
+                )
+                .await?;
+                report.repaired.push(filename.to_string());
+            } else {
+                self.reporter.line(format_args!("  ✓ {} is intact", desc));
+                report.intact.push(filename.to_string());
+            }
+
+            if !symlink_path.exists() || is_link(&symlink_path) {
+                refresh_symlink(&*self.reporter, &target_path, &symlink_path, desc)?;
+            }
+        }
+
+        self.reporter.line(format_args!("\n{}", "=".repeat(60)));
+        self.reporter.line(format_args!(
+            "Repaired {} file(s), left {} intact",
+            report.repaired.len(),
+            report.intact.len()
+        ));
+        for file in &report.repaired {
+            self.reporter.line(format_args!("  ↻ {}", file));
+        }
+        for file in &report.unrepaired {
+            self.reporter
+                .line(format_args!("  ✗ {} is still broken", file));
+        }
+
+        Ok(report)
+    }
+
+    /// Repair every database that has a release installed.
+    pub async fn repair_all_databases(&self) -> Result<Vec<RepairReport>> {
+        let config = self.load_config()?;
+        let mut reports = Vec::new();
+
+        for (db_name, versions) in config.iter() {
+            for genome_version in versions.keys() {
+                let db_dir = self.base_dir.join(db_name).join(genome_version);
+                if self.current_release(db_name, genome_version).is_none()
+                    && latest_release_dir(&db_dir).is_none()
+                {
+                    continue;
+                }
+
+                reports.push(self.repair_database(db_name, genome_version).await?);
+            }
+        }
+
+        if reports.is_empty() {
+            self.reporter
+                .line(format_args!("No downloaded databases to repair"));
+        }
+
+        Ok(reports)
+    }
+
+    /// Compare every index in a release directory against its VCF.
+    fn verify_index_match(&self, dated_dir: &Path) -> Result<()> {
+        let vcf_path = dated_dir.join("clinvar.vcf.gz");
+        for filename in ["clinvar.vcf.gz.tbi", "clinvar.vcf.gz.csi"] {
+            let index_path = dated_dir.join(filename);
+            if !index_path.exists() || !vcf_path.exists() {
+                continue;
+            }
+
+            match vcf::check_index_matches(&vcf_path, &index_path)? {
+                None => self
+                    .reporter
+                    .line(format_args!("  ✓ {} matches the VCF", filename)),
+                Some(mismatch) => {
+                    self.reporter.line(format_args!(
+                        "  ✗ {} does not belong to the VCF: {}",
+                        filename, mismatch
+                    ));
+                    return Err(anyhow::anyhow!(
+                        "{} does not index this VCF: {}",
+                        filename,
+                        mismatch
+                    )
+                    .into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The resolved path of a database's current VCF, for handing to tools
+    /// like bcftools.
+    pub fn database_path(&self, db_name: &str, genome_version: &str) -> Result<PathBuf> {
+        let link = self
+            .base_dir
+            .join(db_name)
+            .join(genome_version)
+            .join("clinvar.vcf.gz");
+        if !link.exists() {
+            return Err(anyhow::anyhow!(
+                "Database {}/{} is not downloaded",
+                db_name,
+                genome_version
+            )
+            .into());
+        }
+        Ok(fs::canonicalize(&link)
+            .with_context(|| format!("Failed to resolve {}", link.display()))?)
+    }
+
+    /// Compare the release date on the mirror with the current local release,
+    /// fetching only the small checksum file (or the VCF's headers).
+    pub async fn check_update(&self, db_name: &str, genome_version: &str) -> Result<UpdateStatus> {
+        let config = self.load_config()?;
+        let files = lookup(&config, db_name, genome_version)?;
+
+        self.apply_host_settings(files)?;
+        let (_, latest) = self.resolve_release(files, false).await?;
+
+        Ok(match self.current_release(db_name, genome_version) {
+            None => UpdateStatus::NotDownloaded { latest },
+            Some(current) if is_newer_release(&latest, &current) => {
+                UpdateStatus::UpdateAvailable { current, latest }
+            }
+            Some(current) => UpdateStatus::UpToDate { current },
+        })
+    }
+
+    /// Hash the current VCF of a downloaded database and compare it with the
+    /// checksum stored beside it, without touching the network.
+    pub fn verify_database(&self, db_name: &str, genome_version: &str) -> Result<VerifyStatus> {
+        let release = self
+            .current_release(db_name, genome_version)
+            .ok_or_else(|| {
+                anyhow::anyhow!("Database {}/{} is not downloaded", db_name, genome_version)
+            })?;
+        let dated_dir = self
+            .base_dir
+            .join(db_name)
+            .join(genome_version)
+            .join(release);
+
+        let vcf = dated_dir.join("clinvar.vcf.gz");
+        let sidecar = dated_dir.join("clinvar.vcf.gz.md5");
+        for path in [&vcf, &sidecar] {
+            if !path.exists() {
+                return Ok(VerifyStatus::Missing {
+                    file: file_name(path),
+                });
+            }
+        }
+
+        let method = self
+            .load_config()
+            .ok()
+            .and_then(|config| {
+                config
+                    .get(db_name)?
+                    .get(genome_version)
+                    .map(DatabaseFiles::checksum_method)
+            })
+            .unwrap_or_default();
+        let (expected, _) =
+            parse_checksum_file(&fs::read_to_string(&sidecar)?, method.algorithm)
+                .with_context(|| format!("Invalid checksum file {}", sidecar.display()))?;
+        let actual = match method.target {
+            ChecksumTarget::Compressed => {
+                calculate_checksum_with_progress(&vcf, method.algorithm, |_, _| {})?
+            }
+            ChecksumTarget::Decompressed => {
+                calculate_checksum_decompressed(&vcf, method.algorithm)?
+            }
+        };
+
+        if actual.eq_ignore_ascii_case(&expected) {
+            Ok(VerifyStatus::Valid)
+        } else {
+            Ok(VerifyStatus::ChecksumMismatch { expected, actual })
+        }
+    }
+
+    /// Check the files of the installed release on disk, without touching
+    /// the network. The VCF is hashed against the stored MD5 sidecar (or only
+    /// checked for gzip without one), reporting `progress(file, bytes_hashed,
+    /// total)` as it goes; indexes and the sidecar get structural checks.
+    /// Returning false from `progress` stops with `Error::Cancelled`.
+    pub fn verify_database_with_progress(
+        &self,
+        db_name: &str,
+        genome_version: &str,
+        progress: impl FnMut(&str, u64, u64) -> bool,
+    ) -> Result<Vec<FileVerification>> {
+        let release = self
+            .current_release(db_name, genome_version)
+            .ok_or_else(|| {
+                anyhow::anyhow!("Database {}/{} is not downloaded", db_name, genome_version)
+            })?;
+        self.verify_release(db_name, genome_version, &release, progress)
+    }
+
+    fn verify_release(
+        &self,
+        db_name: &str,
+        genome_version: &str,
+        release: &str,
+        mut progress: impl FnMut(&str, u64, u64) -> bool,
+    ) -> Result<Vec<FileVerification>> {
+        let dated_dir = self
+            .base_dir
+            .join(db_name)
+            .join(genome_version)
+            .join(release);
+
+        let method = self
+            .load_config()
+            .ok()
+            .and_then(|config| {
+                config
+                    .get(db_name)?
+                    .get(genome_version)
+                    .map(DatabaseFiles::checksum_method)
+            })
+            .unwrap_or_default();
+        let expected_checksum = sidecar_checksum(&dated_dir, method.algorithm);
+
+        let mut results = Vec::new();
+        for filename in [
+            "clinvar.vcf.gz",
+            "clinvar.vcf.gz.tbi",
+            "clinvar.vcf.gz.csi",
+            "clinvar.vcf.gz.md5",
+        ] {
+            let path = dated_dir.join(filename);
+            if filename != "clinvar.vcf.gz" && !path.exists() {
+                continue;
+            }
+
+            let mut checksum_mismatch = None;
+            let problem = (|| -> Result<Option<String>> {
+                if !path.exists() {
+                    return Ok(Some("missing".to_string()));
+                }
+                let size = fs::metadata(&path)?.len();
+                if size == 0 {
+                    return Ok(Some("empty".to_string()));
+                }
+
+                let problem = match filename {
+                    "clinvar.vcf.gz" => match &expected_checksum {
+                        Some(expected) => {
+                            let actual = match method.target {
+                                ChecksumTarget::Compressed => calculate_checksum_cancellable(
+                                    &path,
+                                    method.algorithm,
+                                    |hashed, total| progress(filename, hashed, total),
+                                )?,
+                                ChecksumTarget::Decompressed => {
+                                    calculate_checksum_decompressed(&path, method.algorithm)?
+                                }
+                            };
+                            (!actual.eq_ignore_ascii_case(expected)).then(|| {
+                                checksum_mismatch = Some((expected.clone(), actual));
+                                "checksum mismatch".to_string()
+                            })
+                        }
+                        None => (!vcf::is_gzip(&path)?).then(|| "not gzip-compressed".to_string()),
+                    },
+                    "clinvar.vcf.gz.md5" => {
+                        parse_checksum_file(&fs::read_to_string(&path)?, method.algorithm)
+                            .err()
+                            .map(|e| e.to_string())
+                    }
+                    _ => (!vcf::check_index(&path)?).then(|| "not a valid index".to_string()),
+                };
+                if !progress(filename, size, size) {
+                    return Err(Error::Cancelled);
+                }
+                Ok(problem)
+            })();
+            let problem = match problem {
+                Ok(problem) => problem,
+                Err(e) if e.is_cancelled() => return Err(e),
+                Err(e) => Some(e.to_string()),
+            };
+
+            results.push(FileVerification {
+                file: filename.to_string(),
+                passed: problem.is_none(),
+                problem,
+                checksum_mismatch,
+            });
+        }
+
+        Ok(results)
+    }
+
+    /// Describe what's wrong with a downloaded file, or `None` if it checks
+    /// out.
+    async fn check_file(
+        &self,
+        path: &Path,
+        desc: &str,
+        expected_checksum: Option<&str>,
+        method: ChecksumMethod,
+    ) -> Result<Option<String>> {
+        if !path.exists() {
+            return Ok(Some("missing".to_string()));
+        }
+        if fs::metadata(path)?.len() == 0 {
+            return Ok(Some("empty".to_string()));
+        }
+
+        let problem = match desc {
+            "VCF" => match expected_checksum {
+                Some(md5) => match self.check_md5(path, md5, method).await {
+                    Ok(()) => None,
+                    Err(Error::ChecksumMismatch { .. }) => Some("checksum mismatch".to_string()),
+                    Err(e) => return Err(e),
+                },
+                None => (!vcf::is_gzip(path)?).then(|| "not gzip-compressed".to_string()),
+            },
+            "MD5" => parse_checksum_file(&fs::read_to_string(path)?, method.algorithm)
+                .err()
+                .map(|e| e.to_string()),
+            _ => (!vcf::check_index(path)?).then(|| "not a valid index".to_string()),
+        };
+
+        Ok(problem)
+    }
+
+    /// The dated release the VCF symlink currently points at, if any.
+    pub fn current_release(&self, db_name: &str, genome_version: &str) -> Option<String> {
+        let link = self
+            .base_dir
+            .join(db_name)
+            .join(genome_version)
+            .join("clinvar.vcf.gz");
+
+        read_link(&link)
+            .ok()?
+            .parent()?
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    }
+
+    /// The manifest of the current release of a database, recording where
+    /// and when its files were downloaded.
+    pub fn read_manifest(&self, db_name: &str, genome_version: &str) -> Result<Manifest> {
+        let release = self
+            .current_release(db_name, genome_version)
+            .ok_or_else(|| anyhow::anyhow!("{}/{} is not downloaded", db_name, genome_version))?;
+        read_manifest_in(
+            &self
+                .base_dir
+                .join(db_name)
+                .join(genome_version)
+                .join(release),
+        )
+    }
+
+    fn apply_host_settings(&self, files: &DatabaseFiles) -> Result<()> {
+        let indexes = files.indexes().into_iter().map(|(_, url, _)| url);
+        for url in [files.vcf.as_str(), files.md5.as_str()]
+            .into_iter()
+            .chain(files.vcf_mirrors.iter().map(String::as_str))
+            .chain(indexes)
+        {
+            if let Some(limits) = files.host_limits {
+                self.downloader.set_host_limits(url, limits)?;
+            }
+            if !files.supports_head {
+                self.downloader.disable_head(url)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Download from each of `urls` in turn until one yields a file that
+    /// passes verification. When every mirror fails, the error lists what
+    /// went wrong with each.
+    async fn download_and_verify(
+        &self,
+        ids: (&str, &str),
+        urls: &[&str],
+        target_path: &Path,
+        desc: &str,
+        expected: Option<(&str, ChecksumMethod)>,
+        request_options: &RequestOptions,
+    ) -> Result<Fetched> {
+        let mut failures = Vec::new();
+        for url in urls {
+            let e = match self
+                .fetch_and_verify(ids, url, target_path, desc, expected, request_options)
+                .await
+            {
+                Ok(verified) => {
+                    return Ok(Fetched {
+                        url: url.to_string(),
+                        verified,
+                    })
+                }
+                Err(e) => e,
+            };
+            // A mismatch names the file already; keep it matchable.
+            if urls.len() == 1 && matches!(e, Error::ChecksumMismatch { .. }) {
+                return Err(e);
+            }
+            if urls.len() == 1 || self.budget_exhausted() {
+                return Err(anyhow::Error::new(e)
+                    .context(format!("Failed to download {}", desc))
+                    .into());
+            }
+            self.reporter
+                .line(format_args!("    ✗ {} failed: {}", url, e));
+            failures.push(format!("  {}: {}", url, e));
+        }
+
+        Err(anyhow::anyhow!(
+            "Failed to download {} from any of {} mirrors:\n{}",
+            desc,
+            urls.len(),
+            failures.join("\n")
+        )
+        .into())
+    }
+
+    async fn fetch_and_verify(
+        &self,
+        (db_name, genome_version): (&str, &str),
+        url: &str,
+        target_path: &Path,
+        desc: &str,
+        expected: Option<(&str, ChecksumMethod)>,
+        request_options: &RequestOptions,
+    ) -> Result<bool> {
+        let file = target_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        self.reporter
+            .line(format_args!("  ↓ Downloading {}...", desc));
+        self.events.emit(GladeEvent::DownloadStarted {
+            database: db_name.to_string(),
+            genome_version: genome_version.to_string(),
+            file: file.clone(),
+            url: url.to_string(),
+        });
+        let mut journal = DownloadJournal::new(url, target_path, expected.map(|(md5, _)| md5));
+        journal.save()?;
+        let download = if self.vcf_to_stdout && file == "clinvar.vcf.gz" {
+            self.downloader
+                .download_to_part_tee(url, target_path, request_options, &mut tokio::io::stdout())
+                .await
+        } else {
+            self.downloader
+                .download_to_part(url, target_path, request_options)
+                .await
+        };
+        let part = match download {
+            Ok(part) => part,
+            Err(e) => {
+                if part_path(target_path).exists() {
+                    let _ = journal.checkpoint();
+                } else {
+                    DownloadJournal::remove(target_path)?;
+                }
+                return Err(e);
+            }
+        };
+        DownloadJournal::remove(target_path)?;
+        self.reporter.line(format_args!("    ✓ Download complete"));
+
+        if file.ends_with(".vcf.gz") {
+            if let Err(e) = validate_bgzip(&part) {
+                fs::remove_file(&part)?;
+                return Err(e);
+            }
+        }
+
+        let mut verified = false;
+        if let Some((md5, method)) = expected {
+            self.reporter.partial(format_args!(
+                "    Verifying {} checksum... ",
+                method.algorithm
+            ));
+
+            let check = self.check_md5(&part, md5, method).await;
+            if let Some(valid) = checksum_outcome(&check) {
+                self.emit_verified(db_name, genome_version, &file, valid);
+            }
+
+            match check {
+                Ok(()) => {
+                    self.reporter.line(format_args!("✓ Valid"));
+                    verified = true;
+                }
+                Err(Error::ChecksumMismatch {
+                    expected, actual, ..
+                }) => {
+                    self.reporter.line(format_args!("✗ Invalid checksum!"));
+                    fs::remove_file(&part)?;
+                    return Err(Error::ChecksumMismatch {
+                        path: target_path.to_path_buf(),
+                        expected,
+                        actual,
+                    });
+                }
+                Err(e) => {
+                    self.reporter
+                        .line(format_args!("⚠ Could not verify: {}", e));
+                }
+            }
+        }
+
+        fs::rename(&part, target_path).context("Failed to move download into place")?;
+        Ok(verified)
+    }
+
+    pub async fn download_all_databases(&self) -> Result<()> {
+        self.download_all_databases_with_options(&DownloadOptions::default())
+            .await
+    }
+
+    pub async fn download_all_databases_with_options(
+        &self,
+        options: &DownloadOptions,
+    ) -> Result<()> {
+        let config = self.load_config()?;
+        let mut targets = Vec::new();
+        let mut unavailable = Vec::new();
+
+        for (db_name, versions) in config.iter() {
+            if let Some(filter) = &options.genome_version_filter {
+                if !versions.contains_key(filter) {
+                    unavailable.push(db_name.as_str());
+                }
+            }
+
+            for genome_version in versions.keys() {
+                if options
+                    .genome_version_filter
+                    .as_ref()
+                    .is_none_or(|filter| filter == genome_version)
+                {
+                    targets.push((db_name.as_str(), genome_version.as_str()));
+                }
+            }
+        }
+
+        // Up to `jobs` databases download at once; each one's failure is
+        // collected rather than stopping the others.
+        let results: Vec<_> = futures_util::stream::iter(targets)
+            .map(|(db_name, genome_version)| async move {
+                if self.budget_exhausted() {
+                    return (db_name, genome_version, None);
+                }
+                let result = match self.start_download(db_name, genome_version, options).await {
+                    Ok(Some(pending)) => self.finish_pending(pending).await,
+                    Ok(None) => Ok(()),
+                    Err(e) => {
+                        let result = Err(e);
+                        self.report_failure(db_name, genome_version, &result);
+                        result
+                    }
+                };
+                (db_name, genome_version, Some(result))
+            })
+            .buffer_unordered(self.jobs)
+            .collect()
+            .await;
+
+        let mut skipped = Vec::new();
+        let mut failed = Vec::new();
+        for (db_name, genome_version, result) in results {
+            match result {
+                Some(Ok(())) => {}
+                // Downloads cut short by the budget are reported as skipped.
+                Some(Err(_)) | None if self.budget_exhausted() => {
+                    skipped.push(format!("{}/{}", db_name, genome_version));
+                }
+                None => {}
+                Some(Err(e)) => failed.push((format!("{}/{}", db_name, genome_version), e)),
+            }
+        }
+        skipped.sort_unstable();
+        failed.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+        if let Some(filter) = &options.genome_version_filter {
+            if unavailable.len() == config.len() {
+                return Err(anyhow::anyhow!("No database has genome version '{}'", filter).into());
+            }
+            if !unavailable.is_empty() {
+                unavailable.sort_unstable();
+                self.reporter.line(format_args!(
+                    "\nSkipped because they have no {} version:",
+                    filter
+                ));
+                for db_name in unavailable {
+                    self.reporter.line(format_args!("  {}", db_name));
+                }
+            }
+        }
+
+        if failed.len() > 1 {
+            self.reporter.line(format_args!("\nFailed to download:"));
+            for (key, e) in &failed {
+                self.reporter
+                    .line(format_args!("  {}: {}", key, e.message()));
+            }
+        }
+        let skipped = self.report_skipped(&skipped);
+        match failed.len() {
+            0 => skipped,
+            1 => Err(failed.remove(0).1),
+            n => Err(anyhow::anyhow!("{} database(s) failed to download", n).into()),
+        }
+    }
+
+    async fn finish_pending(&self, pending: PendingDownload) -> Result<()> {
+        let (db_name, genome_version) = (pending.db_name.clone(), pending.genome_version.clone());
+        let result = self.finish_download(pending).await;
+        self.report_failure(&db_name, &genome_version, &result);
+        result
+    }
+
+    /// Every catalog entry with its URLs and local state, sorted by database
+    /// and genome version.
+    pub fn databases_status(&self) -> Result<Vec<DatabaseStatus>> {
+        let config = self.load_config()?;
+        let mut statuses = Vec::new();
+
+        for (db_name, versions) in config.iter() {
+            for (genome_version, files) in versions.iter() {
+                let directory = self.base_dir.join(db_name).join(genome_version);
+                let vcf_path = fs::canonicalize(directory.join("clinvar.vcf.gz")).ok();
+                let release = vcf_path
+                    .as_ref()
+                    .and_then(|path| path.parent())
+                    .and_then(|dir| dir.file_name())
+                    .map(|name| name.to_string_lossy().into_owned());
+
+                statuses.push(DatabaseStatus {
+                    database: db_name.clone(),
+                    genome_version: genome_version.clone(),
+                    short_name: files.short_name.clone(),
+                    vcf_url: files.vcf.clone(),
+                    tbi_url: files.tbi.clone(),
+                    csi_url: files.csi.clone(),
+                    md5_url: files.md5.clone(),
+                    downloaded: vcf_path.is_some(),
+                    directory,
+                    vcf_path,
+                    release,
+                });
+            }
+        }
+
+        statuses.sort_by(|a, b| {
+            (&a.database, &a.genome_version).cmp(&(&b.database, &b.genome_version))
+        });
+        Ok(statuses)
+    }
+
+    pub fn list_databases(&self) -> Result<()> {
+        let statuses = self.databases_status()?;
+
+        println!("Available databases:");
+        println!("{}", "=".repeat(60));
+
+        let mut current_db = None;
+        for status in &statuses {
+            if current_db != Some(&status.database) {
+                println!("\nDatabase: {}", status.database);
+                current_db = Some(&status.database);
+            }
+            match &status.short_name {
+                Some(short_name) => println!(
+                    "  Genome Version: {} (short name: {})",
+                    status.genome_version, short_name
+                ),
+                None => println!("  Genome Version: {}", status.genome_version),
+            }
+            println!("    VCF: {}", status.vcf_url);
+            if let Some(tbi) = &status.tbi_url {
+                println!("    TBI: {}", tbi);
+            }
+            if let Some(csi) = &status.csi_url {
+                println!("    CSI: {}", csi);
+            }
+            println!("    MD5: {}", status.md5_url);
+
+            if status.downloaded {
+                println!("    Status: ✓ Downloaded to {}", status.directory.display());
+            } else {
+                println!("    Status: Not downloaded");
+            }
+        }
+
+        println!("\n{}", "=".repeat(60));
+        println!("Use 'glade database download --database <NAME> --genome-version <VERSION>' to download");
+        println!("Use 'glade database download --all' to download all databases");
+
+        Ok(())
+    }
+
+    /// Check every catalog entry's mirror without downloading any VCF: a HEAD
+    /// for availability, size and date, and a fetch of the published
+    /// checksum. Failures are recorded per entry rather than returned.
+    pub async fn survey(&self) -> Result<Vec<SurveyEntry>> {
+        let config = self.load_config()?;
+        let mut entries = Vec::new();
+
+        for (db_name, versions) in config.iter() {
+            for (genome_version, files) in versions.iter() {
+                entries.push(self.survey_one(db_name, genome_version, files).await?);
+            }
+        }
+
+        entries.sort_by(|a, b| {
+            (&a.database, &a.genome_version).cmp(&(&b.database, &b.genome_version))
+        });
+        Ok(entries)
+    }
+
+    async fn survey_one(
+        &self,
+        db_name: &str,
+        genome_version: &str,
+        files: &DatabaseFiles,
+    ) -> Result<SurveyEntry> {
+        self.apply_host_settings(files)?;
+        let request_options = files.request_options()?;
+
+        let (headers, error) = match self.downloader.head(&files.vcf, &request_options).await {
+            Ok(headers) => (Some(headers), None),
+            Err(e) => (None, Some(e.message())),
+        };
+        let header = |name: reqwest::header::HeaderName| {
+            headers
+                .as_ref()
+                .and_then(|headers| headers.get(name))
+                .and_then(|value| value.to_str().ok())
+        };
+        let size = header(reqwest::header::CONTENT_LENGTH).and_then(|size| size.parse().ok());
+        let last_modified = header(reqwest::header::LAST_MODIFIED);
+
+        let (checksum, checksum_date, checksum_error) = match files
+            .checksum_source
+            .fetch(&self.downloader, files, &request_options)
+            .await
+        {
+            Ok((checksum, date)) => (Some(checksum), date, None),
+            Err(e) => (None, None, Some(e.message())),
+        };
+        let remote_release =
+            checksum_date.or_else(|| last_modified.map(|date| fallback_release_date(Some(date))));
+
+        let local_release = self.current_release(db_name, genome_version);
+        let status = match (&local_release, &remote_release, &checksum) {
+            (None, _, _) => ReleaseStatus::NotDownloaded,
+            // Checksum-layout releases are named by a prefix of the VCF's MD5.
+            (Some(local), _, Some(checksum))
+                if parse_release_date(local).is_none() && files.checksum_algo.is_md5() =>
+            {
+                if checksum.starts_with(local.as_str()) {
+                    ReleaseStatus::UpToDate
+                } else {
+                    ReleaseStatus::UpdateAvailable
+                }
+            }
+            (Some(local), Some(remote), _) if parse_release_date(local).is_some() => {
+                if is_newer_release(remote, local) {
+                    ReleaseStatus::UpdateAvailable
+                } else if is_newer_release(local, remote) {
+                    ReleaseStatus::LocalNewer
+                } else {
+                    ReleaseStatus::UpToDate
+                }
+            }
+            _ => ReleaseStatus::Unknown,
+        };
+
+        Ok(SurveyEntry {
+            database: db_name.to_string(),
+            genome_version: genome_version.to_string(),
+            vcf_url: files.vcf.clone(),
+            available: error.is_none(),
+            error,
+            size,
+            remote_release,
+            checksum,
+            checksum_algo: files.checksum_algo,
+            checksum_error,
+            local_release,
+            status,
+        })
+    }
+
+    /// Find database and genome version directories that the catalog no
+    /// longer lists, e.g. after a database was dropped from it. Old releases
+    /// of databases still in the catalog are not orphans.
+    pub fn find_orphans(&self) -> Result<Vec<Orphan>> {
+        let config = self.load_config()?;
+        let mut orphans = Vec::new();
+
+        let Ok(entries) = fs::read_dir(&self.base_dir) else {
+            return Ok(orphans);
+        };
+        for entry in entries {
+            let db_path = entry?.path();
+            let db_name = file_name(&db_path);
+            if !db_path.is_dir() || db_name == OBJECTS_DIR || db_name.starts_with('.') {
+                continue;
+            }
+
+            let Some(versions) = config.get(&db_name) else {
+                orphans.push(Orphan {
+                    recognised: fs::read_dir(&db_path)?
+                        .filter_map(|entry| entry.ok())
+                        .any(|entry| is_download_dir(&entry.path())),
+                    size: dir_size(&db_path)?,
+                    path: db_path,
+                    database: db_name,
+                    genome_version: None,
+                });
+                continue;
+            };
+
+            for entry in fs::read_dir(&db_path)? {
+                let version_path = entry?.path();
+                let genome_version = file_name(&version_path);
+                if !version_path.is_dir() || versions.contains_key(&genome_version) {
+                    continue;
+                }
+                orphans.push(Orphan {
+                    recognised: is_download_dir(&version_path),
+                    size: dir_size(&version_path)?,
+                    path: version_path,
+                    database: db_name.clone(),
+                    genome_version: Some(genome_version),
+                });
+            }
+        }
+
+        orphans.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(orphans)
+    }
+
+    /// Delete an orphaned download. Unrecognised directories are refused.
+    pub fn remove_orphan(&self, orphan: &Orphan) -> Result<()> {
+        if !orphan.recognised {
+            return Err(anyhow::anyhow!(
+                "Refusing to delete {}: it doesn't look like a glade download",
+                orphan.path.display()
+            )
+            .into());
+        }
+
+        remove_tree(&orphan.path)?;
+        self.collect_garbage()?;
+        Ok(())
+    }
+
+    /// Delete stored objects that no release links to any more, returning
+    /// the bytes freed. Where link counts aren't available, nothing is
+    /// collected.
+    fn collect_garbage(&self) -> Result<u64> {
+        let Ok(entries) = fs::read_dir(self.base_dir.join(OBJECTS_DIR)) else {
+            return Ok(0);
+        };
+
+        let mut freed = 0;
+        for entry in entries {
+            let path = entry?.path();
+            let metadata = fs::symlink_metadata(&path)?;
+            if metadata.is_file() && link_count(&metadata) == Some(1) {
+                fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+                freed += metadata.len();
+            }
+        }
+        Ok(freed)
+    }
+
+    /// Delete all but the newest `keep` dated releases of a database. The
+    /// release the symlinks point at, and one waiting to be promoted, are
+    /// always kept, even when older than the cutoff.
+    pub fn prune(&self, db_name: &str, genome_version: &str, keep: usize) -> Result<PruneReport> {
+        let db_dir = self.base_dir.join(db_name).join(genome_version);
+        if !db_dir.is_dir() {
+            return Err(anyhow::anyhow!(
+                "Database {}/{} is not downloaded",
+                db_name,
+                genome_version
+            )
+            .into());
+        }
+
+        let mut releases: Vec<String> = fs::read_dir(&db_dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| parse_release_date(name).is_some())
+            .collect();
+        releases.sort_by_key(|release| std::cmp::Reverse(parse_release_date(release)));
+
+        // Checksum-layout releases are date symlinks to an md5- directory, so
+        // compare where each release resolves to rather than its name.
+        let live_dir = fs::canonicalize(db_dir.join("clinvar.vcf.gz"))
+            .ok()
+            .and_then(|vcf| vcf.parent().map(Path::to_path_buf));
+        let pending = self
+            .load_state()?
+            .pending_promotions
+            .remove(&format!("{}/{}", db_name, genome_version))
+            .map(|pending| pending.release);
+
+        let mut report = PruneReport::default();
+        let mut kept_dirs = Vec::new();
+        let mut unlinked_dirs = Vec::new();
+        for (index, release) in releases.iter().enumerate() {
+            let path = db_dir.join(release);
+            let resolved = fs::canonicalize(&path)?;
+            let protected =
+                live_dir.as_ref() == Some(&resolved) || pending.as_ref() == Some(release);
+            if index < keep || protected {
+                report.kept.push(release.clone());
+                kept_dirs.push(resolved);
+                continue;
+            }
+
+            if path.is_symlink() {
+                fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+                unlinked_dirs.push(resolved);
+            } else {
+                report.freed += remove_tree(&path)?;
+            }
+            report.removed.push(release.clone());
+        }
+
+        // A checksum-named directory goes once no kept release points at it.
+        for dir in unlinked_dirs {
+            if dir.is_dir() && !kept_dirs.contains(&dir) && live_dir.as_ref() != Some(&dir) {
+                report.freed += remove_tree(&dir)?;
+            }
+        }
+        report.freed += self.collect_garbage()?;
+
+        report.removed.reverse();
+        report.kept.reverse();
+        Ok(report)
+    }
+
+    /// Delete every release and symlink of a downloaded database, returning
+    /// the bytes freed. Stored objects no other release links to go with it.
+    pub fn remove_database(&self, db_name: &str, genome_version: &str) -> Result<u64> {
+        let db_dir = self.base_dir.join(db_name).join(genome_version);
+        if !db_dir.is_dir() {
+            return Err(anyhow::anyhow!(
+                "Database {}/{} is not downloaded",
+                db_name,
+                genome_version
+            )
+            .into());
+        }
+
+        let size = remove_tree(&db_dir)? + self.collect_garbage()?;
+
+        // Drop the database directory once its last version is gone.
+        let parent = self.base_dir.join(db_name);
+        if fs::read_dir(&parent)?.next().is_none() {
+            fs::remove_dir(&parent)?;
+        }
+
+        let key = format!("{}/{}", db_name, genome_version);
+        self.update_state(|state| {
+            state.releases.remove(&key);
+            state.pending_promotions.remove(&key);
+        })?;
+
+        Ok(size)
+    }
+
+    /// Delete everything under the data directory, including the
+    /// content-addressed store and run state, returning the bytes freed.
+    pub fn remove_all_databases(&self) -> Result<u64> {
+        let mut size = 0;
+        for entry in fs::read_dir(&self.base_dir)? {
+            size += remove_tree(&entry?.path())?;
+        }
+        Ok(size)
+    }
+
+    /// Where downloaded databases are kept.
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    /// Print one CSV row per database/version: its download status, the
+    /// release date and size of the current VCF, where it lives, and the
+    /// stored checksum.
+    pub fn list_databases_csv(&self) -> Result<()> {
+        println!("database,genome_version,status,date,size,path,checksum");
+
+        for status in self.databases_status()? {
+            let vcf_path = status.vcf_path.as_ref();
+            let state = if status.downloaded {
+                "downloaded"
+            } else {
+                "not_downloaded"
+            };
+            let size = vcf_path
+                .and_then(|path| fs::metadata(path).ok())
+                .map(|meta| meta.len().to_string())
+                .unwrap_or_default();
+            let checksum = vcf_path
+                .and_then(|path| fs::read_to_string(path.with_extension("gz.md5")).ok())
+                .and_then(|content| content.split_whitespace().next().map(str::to_string))
+                .unwrap_or_default();
+            let path = vcf_path
+                .map(|path| path.display().to_string())
+                .unwrap_or_default();
+
+            let row = [
+                status.database.as_str(),
+                status.genome_version.as_str(),
+                state,
+                status.release.as_deref().unwrap_or_default(),
+                &size,
+                &path,
+                &checksum,
+            ];
+            println!(
+                "{}",
+                row.iter()
+                    .map(|field| csv_field(field))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            );
+        }
+
+        Ok(())
+    }
+
+    fn load_state(&self) -> Result<RunState> {
+        let path = self.base_dir.join(STATE_FILE);
+        if !path.exists() {
+            return Ok(RunState::default());
+        }
+
+        let content = fs::read_to_string(&path).context("Failed to read state file")?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    fn save_state(&self, state: &RunState) -> Result<()> {
+        let path = self.base_dir.join(STATE_FILE);
+        fs::write(&path, serde_json::to_string_pretty(state)?)
+            .context("Failed to write state file")?;
+        Ok(())
+    }
+
+    /// Re-read the state file, apply `update` and write it back, so changes
+    /// made elsewhere in the run aren't overwritten.
+    fn update_state(&self, update: impl FnOnce(&mut RunState)) -> Result<()> {
+        let mut state = self.load_state()?;
+        update(&mut state);
+        self.save_state(&state)
+    }
+
+    /// Record a verified release as waiting to be promoted, keeping the
+    /// original deadline if the same release was already waiting. Returns
+    /// when it becomes due.
+    fn defer_promotion(
+        &self,
+        db_name: &str,
+        genome_version: &str,
+        release: &str,
+        delay: Duration,
+    ) -> Result<String> {
+        let key = format!("{}/{}", db_name, genome_version);
+        let promote_after = chrono::Duration::from_std(delay)
+            .ok()
+            .and_then(|delay| Utc::now().checked_add_signed(delay))
+            .ok_or_else(|| anyhow::anyhow!("Promotion delay of {}s is too long", delay.as_secs()))?
+            .to_rfc3339();
+
+        let mut due = promote_after.clone();
+        self.update_state(|state| {
+            let pending = state
+                .pending_promotions
+                .entry(key)
+                .or_insert_with(|| PendingPromotion {
+                    release: release.to_string(),
+                    promote_after: promote_after.clone(),
+                });
+            if pending.release != release {
+                pending.release = release.to_string();
+                pending.promote_after = promote_after;
+            }
+            due = pending.promote_after.clone();
+        })?;
+
+        Ok(due)
+    }
+
+    /// Promote a held-back release whose delay has run out.
+    fn promote_if_due(&self, db_name: &str, genome_version: &str) -> Result<()> {
+        let key = format!("{}/{}", db_name, genome_version);
+        let state = self.load_state()?;
+        let Some(pending) = state.pending_promotions.get(&key) else {
+            return Ok(());
+        };
+
+        let due = chrono::DateTime::parse_from_rfc3339(&pending.promote_after)
+            .map_or(true, |after| after <= Utc::now());
+        if due {
+            self.promote(db_name, genome_version)?;
+        }
+
+        Ok(())
+    }
+
+    /// Point the stable symlinks at the held-back release now, regardless of
+    /// its promote delay. Returns the promoted release, or `None` if nothing
+    /// was waiting.
+    pub fn promote(&self, db_name: &str, genome_version: &str) -> Result<Option<String>> {
+        let key = format!("{}/{}", db_name, genome_version);
+        let Some(pending) = self.load_state()?.pending_promotions.remove(&key) else {
+            return Ok(None);
+        };
+
+        let db_dir = self.base_dir.join(db_name).join(genome_version);
+        let dated_dir = db_dir.join(&pending.release);
+        if !dated_dir.join("clinvar.vcf.gz").exists() {
+            return Err(anyhow::anyhow!(
+                "Release {} of {} is no longer on disk",
+                pending.release,
+                key
+            )
+            .into());
+        }
+
+        self.reporter.line(format_args!(
+            "  ↑ Promoting {} release {}",
+            key, pending.release
+        ));
+        link_release(&*self.reporter, &db_dir, &dated_dir)?;
+
+        self.update_state(|state| {
+            state.pending_promotions.remove(&key);
+        })?;
+
+        Ok(Some(pending.release))
+    }
+
+    /// Adopt a release directory filled outside glade, e.g. by rsync from
+    /// another mirror, as the current release: check its files as `verify`
+    /// would and, if they pass, point the symlinks at it and write its
+    /// manifest.
+    pub fn touch(
+        &self,
+        db_name: &str,
+        genome_version: &str,
+        release: &str,
+    ) -> Result<Vec<FileVerification>> {
+        if parse_release_date(release).is_none() {
+            return Err(anyhow::anyhow!("'{}' is not a YYYYMMDD release date", release).into());
+        }
+
+        let config = self.load_config()?;
+        let version_config = lookup(&config, db_name, genome_version)?;
+
+        let db_dir = self.base_dir.join(db_name).join(genome_version);
+        let dated_dir = db_dir.join(release);
+        if !dated_dir.is_dir() {
+            return Err(anyhow::anyhow!("{} does not exist", dated_dir.display()).into());
+        }
+
+        let results = self.verify_release(db_name, genome_version, release, |_, _, _| true)?;
+        if results.iter().all(|result| result.passed) {
+            link_release(&*self.reporter, &db_dir, &dated_dir)?;
+            write_manifest(
+                &dated_dir,
+                &adopted_manifest(db_name, genome_version, release, &dated_dir, version_config)?,
+            )?;
+        }
+
+        Ok(results)
+    }
+
+    /// Download the databases listed in a text file, one `database
+    /// genome_version` (or short name) per line. Blank lines and `#` comments
+    /// are ignored. Unknown entries and failed downloads are reported at the
+    /// end without stopping the others.
+    pub async fn download_from_file(&self, path: &Path, options: &DownloadOptions) -> Result<()> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read database list {}", path.display()))?;
+        let config = self.load_config()?;
+
+        let mut downloaded = Vec::new();
+        let mut failed = Vec::new();
+        let mut skipped = Vec::new();
+
+        for (index, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut fields = line.split_whitespace();
+            let name = fields.next().unwrap_or_default();
+            let genome_version = fields.next();
+            let entry = if fields.next().is_some() {
+                Err(anyhow::anyhow!("expected 'database genome_version'").into())
+            } else {
+                resolve_database(&config, name, genome_version).and_then(|(db_name, version)| {
+                    lookup(&config, &db_name, &version)?;
+                    Ok((db_name, version))
+                })
+            };
+
+            let (db_name, genome_version) = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    self.reporter
+                        .line(format_args!("  ✗ Line {}: {}", index + 1, e));
+                    failed.push(format!("line {}: {}", index + 1, line));
+                    continue;
+                }
+            };
+
+            let key = format!("{}/{}", db_name, genome_version);
+            if self.budget_exhausted() {
+                skipped.push(key);
+                continue;
+            }
+
+            match self
+                .download_database_with_options(&db_name, &genome_version, options)
+                .await
+            {
+                Ok(()) => downloaded.push(key),
+                Err(_) if self.budget_exhausted() => skipped.push(key),
+                Err(e) => {
+                    self.reporter
+                        .line(format_args!("  ✗ {} failed: {}", key, e));
+                    failed.push(key);
+                }
+            }
+        }
+
+        self.reporter.line(format_args!("\n{}", "=".repeat(60)));
+        self.reporter.line(format_args!(
+            "Downloaded {} database(s) from {}",
+            downloaded.len(),
+            path.display()
+        ));
+        for key in &downloaded {
+            self.reporter.line(format_args!("  ✓ {}", key));
+        }
+        for key in &failed {
+            self.reporter.line(format_args!("  ✗ {}", key));
+        }
+
+        self.report_skipped(&skipped)?;
+        if !failed.is_empty() {
+            return Err(anyhow::anyhow!("{} entries could not be downloaded", failed.len()).into());
+        }
+
+        Ok(())
+    }
+
+    /// Download only the databases whose remote release date is newer than the
+    /// one recorded by the previous successful run.
+    pub async fn download_since_last_run(
+        &self,
+        options: &DownloadOptions,
+    ) -> Result<Vec<ReleaseChange>> {
+        let config = self.load_config()?;
+        let state = self.load_state()?;
+        let mut changes = Vec::new();
+        let mut skipped = Vec::new();
+
+        if let Some(last_run) = &state.last_run {
+            self.reporter
+                .line(format_args!("Last successful run: {}", last_run));
+        } else {
+            self.reporter.line(format_args!(
+                "No previous run recorded; checking every database"
+            ));
+        }
+
+        for (db_name, versions) in config.iter() {
+            for (genome_version, files) in versions.iter() {
+                let key = format!("{}/{}", db_name, genome_version);
+                if self.budget_exhausted() {
+                    skipped.push(key);
+                    continue;
+                }
+
+                self.apply_host_settings(files)?;
+                let (_, date) = self
+                    .resolve_release(files, options.require_checksum)
+                    .await
+                    .with_context(|| format!("Failed to resolve release of {}", key))?;
+                let previous = state.releases.get(&key).cloned();
+
+                if previous
+                    .as_deref()
+                    .is_some_and(|seen| !is_newer_release(&date, seen))
+                {
+                    self.reporter
+                        .line(format_args!("  ✓ {} unchanged ({})", key, date));
+                    continue;
+                }
+
+                let result = self
+                    .download_database_with_options(db_name, genome_version, options)
+                    .await;
+                if result.is_err() && self.budget_exhausted() {
+                    skipped.push(key);
+                    continue;
+                }
+                result?;
+                if options.dry_run {
+                    continue;
+                }
+
+                // Persist as we go so a later failure doesn't discard progress.
+                self.update_state(|state| {
+                    state.releases.insert(key, date.clone());
+                })?;
+                changes.push(ReleaseChange {
+                    db_name: db_name.clone(),
+                    genome_version: genome_version.clone(),
+                    previous,
+                    current: date,
+                });
+            }
+        }
+
+        // A run cut short by the budget isn't a successful run, and a dry
+        // run isn't a run at all.
+        if skipped.is_empty() && !options.dry_run {
+            self.update_state(|state| {
+                state.last_run = Some(chrono::Utc::now().to_rfc3339());
+            })?;
+        }
+
+        self.reporter.line(format_args!("\n{}", "=".repeat(60)));
+        if changes.is_empty() {
+            self.reporter
+                .line(format_args!("No databases changed since the last run"));
+        } else {
+            self.reporter
+                .line(format_args!("Changed since the last run:"));
+            for change in &changes {
+                self.reporter.line(format_args!(
+                    "  {}/{}: {} -> {}",
+                    change.db_name,
+                    change.genome_version,
+                    change.previous.as_deref().unwrap_or("none"),
+                    change.current
+                ));
+            }
+        }
+
+        self.report_skipped(&skipped)?;
+        Ok(changes)
+    }
+}
+
+fn create_base_dir(base_dir: &Path) -> Result<()> {
+    fs::create_dir_all(base_dir).with_context(|| {
+        format!(
+            "Failed to create base directory {}{}",
+            base_dir.display(),
+            home_hint()
+        )
+    })?;
+    Ok(())
+}
+
+/// Extra guidance for home directory failures. Services started by systemd
+/// and similar usually run without `HOME`, which is the common cause.
+fn home_hint() -> &'static str {
+    home_hint_for(std::env::var_os("HOME").as_deref())
+}
+
+fn home_hint_for(home: Option<&std::ffi::OsStr>) -> &'static str {
+    if home.is_some_and(|home| !home.is_empty()) {
+        ""
+    } else {
+        " (HOME is not set, as is usual when running as a service; set HOME or \
+         GLADE_HOME in the service's environment to a writable directory, or \
+         pass --base-dir)"
+    }
+}
+
+/// Create the checksum-named directory for a release and point `<date>` at
+/// it, replacing a `<date>` symlink left by different content.
+fn link_checksum_dir(reporter: &dyn Reporter, db_dir: &Path, date: &str, md5: &str) -> Result<()> {
+    let prefix = md5
+        .get(..CHECKSUM_PREFIX_LEN)
+        .unwrap_or(md5)
+        .to_ascii_lowercase();
+    let content_dir = db_dir.join(format!("md5-{}", prefix));
+    fs::create_dir_all(&content_dir).context("Failed to create database directory")?;
+
+    let dated_dir = db_dir.join(date);
+    if update_symlink(&content_dir, &dated_dir)? {
+        reporter.line(format_args!(
+            "    ✓ {} -> {}",
+            date,
+            content_dir
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default()
+        ));
+    }
+
+    Ok(())
+}
+
+/// The newest dated release directory under `db_dir`, for when the VCF
+/// symlink itself is gone.
+fn latest_release_dir(db_dir: &Path) -> Option<String> {
+    fs::read_dir(db_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| parse_release_date(name).is_some())
+        .max()
+}
+
+/// Whether `candidate` is a later release than `seen`, comparing calendar
+/// dates when both parse and falling back to plain string order otherwise.
+fn is_newer_release(candidate: &str, seen: &str) -> bool {
+    match (parse_release_date(candidate), parse_release_date(seen)) {
+        (Some(candidate), Some(seen)) => candidate > seen,
+        _ => candidate > seen,
+    }
+}
+
+/// Time elapsed since a `YYYYMMDD` release date.
+fn release_age(release: &str) -> Option<Duration> {
+    let date = parse_release_date(release)?;
+    let today = Utc::now().date_naive();
+
+    if date > today {
+        // A release from the future means our clock is behind; treat it as
+        // fresh rather than guessing how stale it is.
+        tracing::warn!(
+            "Release {} is later than today ({}); the system clock may be skewed",
+            release,
+            today.format("%Y%m%d")
+        );
+        return Some(Duration::ZERO);
+    }
+
+    (today - date).to_std().ok()
+}
+
+/// Write the file at `path` to stdout.
+async fn copy_to_stdout(path: &Path) -> Result<()> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut stdout = tokio::io::stdout();
+    tokio::io::copy(&mut file, &mut stdout)
+        .await
+        .context("Failed to write to stdout")?;
+    tokio::io::AsyncWriteExt::flush(&mut stdout)
+        .await
+        .context("Failed to flush stdout")?;
+    Ok(())
+}
+
+/// Whether a checksum check found the file valid, or `None` if it couldn't
+/// tell.
+fn checksum_outcome(result: &Result<()>) -> Option<bool> {
+    match result {
+        Ok(()) => Some(true),
+        Err(Error::ChecksumMismatch { .. }) => Some(false),
+        Err(_) => None,
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// The checksum in a release's sidecar, if it has a readable one.
+fn sidecar_checksum(release_dir: &Path, algorithm: HashAlgorithm) -> Option<String> {
+    fs::read_to_string(release_dir.join("clinvar.vcf.gz.md5"))
+        .ok()
+        .and_then(|content| parse_checksum_file(&content, algorithm).ok())
+        .map(|(checksum, _)| checksum)
+}
+
+/// The manifest of a verified release adopted by `touch`. Files keep the
+/// source an existing manifest records; others are credited to the catalog
+/// URL they would have been downloaded from.
+fn adopted_manifest(
+    db_name: &str,
+    genome_version: &str,
+    release: &str,
+    release_dir: &Path,
+    config: &DatabaseFiles,
+) -> Result<Manifest> {
+    let previous = read_manifest_in(release_dir).ok();
+    let mut catalog = vec![(config.vcf.as_str(), "clinvar.vcf.gz")];
+    catalog.extend(
+        config
+            .indexes()
+            .into_iter()
+            .map(|(_, url, name)| (url, name)),
+    );
+    catalog.push((config.md5.as_str(), "clinvar.vcf.gz.md5"));
+
+    let mut files = Vec::new();
+    for (url, name) in catalog {
+        let path = release_dir.join(name);
+        if !path.is_file() {
+            continue;
+        }
+        let url = previous
+            .as_ref()
+            .and_then(|manifest| manifest.files.iter().find(|f| f.name == name))
+            .map_or(url, |file| file.url.as_str());
+        files.push(ManifestFile {
+            name: name.to_string(),
+            url: url.to_string(),
+            size: fs::metadata(&path)?.len(),
+        });
+    }
+
+    let algorithm = config.checksum_method().algorithm;
+    let expected_checksum = sidecar_checksum(release_dir, algorithm);
+    Ok(Manifest {
+        database: db_name.to_string(),
+        genome_version: genome_version.to_string(),
+        release: release.to_string(),
+        downloaded_at: Utc::now().to_rfc3339(),
+        checksum_algorithm: expected_checksum.as_ref().map(|_| algorithm),
+        // The release passed verification, which hashes the VCF whenever
+        // there's a checksum to hash it against.
+        checksum_verified: expected_checksum.is_some(),
+        expected_checksum,
+        files,
+    })
+}
+
+fn read_manifest_in(release_dir: &Path) -> Result<Manifest> {
+    let path = release_dir.join(MANIFEST_FILE);
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(serde_json::from_str(&content)
+        .with_context(|| format!("Invalid manifest {}", path.display()))?)
+}
+
+/// Write the manifest through a temporary file, so a reader never sees half
+/// of it and a copy hard-linked elsewhere is left alone.
+fn write_manifest(release_dir: &Path, manifest: &Manifest) -> Result<()> {
+    let path = release_dir.join(MANIFEST_FILE);
+    let temp = release_dir.join(format!("{}.tmp", MANIFEST_FILE));
+    fs::write(&temp, serde_json::to_string_pretty(manifest)?)
+        .context("Failed to write manifest")?;
+    fs::rename(&temp, &path).context("Failed to write manifest")?;
+    Ok(())
+}
+
+/// Whether `dir` is a genome version directory glade downloaded into: it
+/// has the VCF link, or a release directory holding a VCF.
+fn is_download_dir(dir: &Path) -> bool {
+    if dir.join("clinvar.vcf.gz").symlink_metadata().is_ok() {
+        return true;
+    }
+
+    fs::read_dir(dir).is_ok_and(|entries| {
+        entries
+            .filter_map(|entry| entry.ok())
+            .any(|entry| entry.path().join("clinvar.vcf.gz").exists())
+    })
+}
+
+/// Total size of the files under `path`, not following symlinks.
+fn dir_size(path: &Path) -> Result<u64> {
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_symlink() {
+        return Ok(0);
+    }
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        size += dir_size(&entry?.path())?;
+    }
+    Ok(size)
+}
+
+/// Delete `path`, a file, symlink or directory tree, returning the bytes
+/// actually freed: a file still hard-linked elsewhere, e.g. from the object
+/// store, frees nothing.
+fn remove_tree(path: &Path) -> Result<u64> {
+    let metadata = fs::symlink_metadata(path)?;
+    let mut freed = 0;
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path)? {
+            freed += remove_tree(&entry?.path())?;
+        }
+        fs::remove_dir(path)
+    } else {
+        if metadata.is_file() && link_count(&metadata).unwrap_or(1) == 1 {
+            freed = metadata.len();
+        }
+        fs::remove_file(path)
+    }
+    .with_context(|| format!("Failed to remove {}", path.display()))?;
+    Ok(freed)
+}
+
+/// How many directory entries link to a file, where the platform says.
+#[cfg(unix)]
+fn link_count(metadata: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.nlink())
+}
+
+#[cfg(not(unix))]
+fn link_count(_metadata: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(unix)]
+fn same_file(a: &Path, b: &Path) -> Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let (a, b) = (fs::metadata(a)?, fs::metadata(b)?);
+    Ok(a.dev() == b.dev() && a.ino() == b.ino())
+}
+
+#[cfg(not(unix))]
+fn same_file(_a: &Path, _b: &Path) -> Result<bool> {
+    Ok(false)
+}
+
+/// Point each stable symlink in `db_dir` at its file in `dated_dir`, where
+/// the release has one.
+fn link_release(reporter: &dyn Reporter, db_dir: &Path, dated_dir: &Path) -> Result<()> {
+    for filename in [
+        "clinvar.vcf.gz",
+        "clinvar.vcf.gz.tbi",
+        "clinvar.vcf.gz.csi",
+        "clinvar.vcf.gz.md5",
+    ] {
+        let target_path = dated_dir.join(filename);
+        let symlink_path = db_dir.join(filename);
+        if target_path.exists() && (!symlink_path.exists() || is_link(&symlink_path)) {
+            refresh_symlink(reporter, &target_path, &symlink_path, filename)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn refresh_symlink(
+    reporter: &dyn Reporter,
+    target_path: &Path,
+    symlink_path: &Path,
+    desc: &str,
+) -> Result<()> {
+    let updated = update_symlink(target_path, symlink_path)
+        .with_context(|| format!("Failed to create symlink for {}", desc))?;
+
+    if updated {
+        reporter.line(format_args!(
+            "    ✓ Updated symlink: {}",
+            symlink_path.display()
+        ));
+    } else {
+        reporter.line(format_args!(
+            "    ✓ Symlink already current: {}",
+            symlink_path.display()
+        ));
+    }
+
+    Ok(())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_config;
+    use crate::test_server::{Response, TestServer};
+
+    const VCF: &[u8] = b"##fileformat=VCFv4.2\n##contig=<ID=1>\n#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n1\t100\t.\tA\tG\t.\t.\t.\n";
+
+    /// Serve each `(path, body)` pair, honouring `Range: bytes=<n>-`, and
+    /// 404 for anything else.
+    async fn serve(files: Vec<(&'static str, Vec<u8>)>) -> TestServer {
+        let files: HashMap<_, _> = files.into_iter().collect();
+        TestServer::start(move |request| {
+            let Some(body) = files.get(request.path.as_str()) else {
+                return Response::new(404, "Not Found");
+            };
+            let start = request
+                .header("range")
+                .and_then(|range| range.strip_prefix("bytes="))
+                .and_then(|range| range.strip_suffix('-'))
+                .and_then(|start| start.parse::<usize>().ok());
+            match start {
+                Some(start) if start < body.len() => Response::new(206, &body[start..]).header(
+                    "Content-Range",
+                    &format!("bytes {}-{}/{}", start, body.len() - 1, body.len()),
+                ),
+                Some(_) => Response::new(416, ""),
+                None => Response::new(200, body.clone()),
+            }
+        })
+        .await
+    }
+
+    /// A bgzipped VCF release dated 2024-01-15 and its MD5 sidecar.
+    fn release() -> Vec<(&'static str, Vec<u8>)> {
+        let vcf = crate::bgzf::compress(VCF, 64 * 1024);
+        let md5 = format!("{:x}  clinvar_20240115.vcf.gz\n", md5::compute(&vcf));
+        vec![
+            ("/clinvar_20240115.vcf.gz", vcf),
+            ("/clinvar_20240115.vcf.gz.md5", md5.into_bytes()),
+        ]
+    }
+
+    /// Write a catalog for `server` whose `testdb` GRCh38 entry has the release's VCF
+    /// and checksum plus `extra` fields, and return a manager reading it.
+    fn manager_for(dir: &tempfile::TempDir, server: &TestServer, extra: &str) -> DatabaseManager {
+        let yaml = format!(
+            "testdb:\n  GRCh38:\n    vcf: {}\n    md5: {}\n{}",
+            server.url("/clinvar_20240115.vcf.gz"),
+            server.url("/clinvar_20240115.vcf.gz.md5"),
+            extra
+        );
+        let path = dir.path().join("catalog.yaml");
+        fs::write(&path, yaml).unwrap();
+        manager(dir).with_config_path(path)
+    }
+
+    fn manager(dir: &tempfile::TempDir) -> DatabaseManager {
+        DatabaseManager::new_in(dir.path().join("databases"))
+            .unwrap()
+            .with_quiet()
+    }
+
+    /// The catalog entry for a database served by `server`, with `extra`
+    /// fields appended.
+    fn files(server: &TestServer, extra: &str) -> DatabaseFiles {
+        let yaml = format!(
+            "clinvar:\n  GRCh38:\n    vcf: {}\n    tbi: {}\n    md5: {}\n{}",
+            server.url("/clinvar.vcf.gz"),
+            server.url("/clinvar.vcf.gz.tbi"),
+            server.url("/clinvar.vcf.gz.md5"),
+            extra
+        );
+        let mut config = parse_config(&yaml).unwrap();
+        config.remove("clinvar").unwrap().remove("GRCh38").unwrap()
+    }
+
+    async fn resolve_with_checksum_file(
+        body: &'static str,
+        require_checksum: bool,
+    ) -> Result<(Option<String>, String)> {
+        let server = TestServer::start(move |_| Response::new(200, body)).await;
+        let dir = tempfile::tempdir().unwrap();
+        manager(&dir)
+            .resolve_release(&files(&server, ""), require_checksum)
+            .await
+    }
+
+    #[tokio::test]
+    async fn require_checksum_refuses_bad_checksum_files() {
+        for (body, reason) in [
+            ("", "MD5 file is empty"),
+            (" \n\t\n", "MD5 file is empty"),
+            (
+                "<html>Not Found</html>",
+                "does not start with a 32-digit hex hash",
+            ),
+        ] {
+            let error = resolve_with_checksum_file(body, true).await.unwrap_err();
+            let message = error.to_string();
+            assert!(
+                message.contains("Refusing to proceed without a valid checksum"),
+                "{}",
+                message
+            );
+            assert!(message.contains(reason), "{}", message);
+        }
+    }
+
+    #[tokio::test]
+    async fn bad_checksum_file_downloads_unverified_without_require_checksum() {
+        let (expected, _) = resolve_with_checksum_file("", false).await.unwrap();
+
+        assert_eq!(expected, None);
+    }
+
+    #[test]
+    fn newer_release_compares_calendar_dates() {
+        assert!(is_newer_release("20240201", "20240131"));
+        assert!(!is_newer_release("20240131", "20240201"));
+        assert!(!is_newer_release("20240131", "20240131"));
+    }
+
+    #[test]
+    fn future_release_is_treated_as_fresh() {
+        let tomorrow = (Utc::now().date_naive() + chrono::Days::new(1))
+            .format("%Y%m%d")
+            .to_string();
+
+        assert_eq!(release_age(&tomorrow), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn release_age_counts_whole_days_in_utc() {
+        let ten_days_ago = (Utc::now().date_naive() - chrono::Days::new(10))
+            .format("%Y%m%d")
+            .to_string();
+
+        assert_eq!(
+            release_age(&ten_days_ago),
+            Some(Duration::from_secs(10 * 86400))
+        );
+        assert_eq!(release_age("not-a-date"), None);
+    }
+
+    #[test]
+    fn missing_home_points_at_glade_home_and_base_dir() {
+        for home in [None, Some(std::ffi::OsStr::new(""))] {
+            let hint = home_hint_for(home);
+            assert!(hint.contains("HOME is not set"), "{}", hint);
+            assert!(hint.contains("GLADE_HOME"), "{}", hint);
+            assert!(hint.contains("--base-dir"), "{}", hint);
+        }
+        assert_eq!(home_hint_for(Some(std::ffi::OsStr::new("/home/glade"))), "");
+    }
+
+    #[test]
+    fn base_dir_failure_names_the_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let blocker = dir.path().join("file");
+        fs::write(&blocker, "").unwrap();
+
+        let error = create_base_dir(&blocker.join("databases")).unwrap_err();
+
+        assert!(error.to_string().contains(&format!(
+            "Failed to create base directory {}",
+            blocker.join("databases").display()
+        )));
+    }
+
+    #[tokio::test]
+    async fn csi_index_is_downloaded_and_linked() {
+        let mut files = release();
+        let csi = crate::bgzf::compress(b"CSI\x01\x0e\0\0\0\x05\0\0\0\0\0\0\0", 1024);
+        files.push(("/clinvar_20240115.vcf.gz.csi", csi.clone()));
+        let server = serve(files).await;
+        let dir = tempfile::tempdir().unwrap();
+        let extra = format!("    csi: {}\n", server.url("/clinvar_20240115.vcf.gz.csi"));
+        let manager = manager_for(&dir, &server, &extra);
+
+        manager.download_database("testdb", "GRCh38").await.unwrap();
+
+        let version_dir = dir.path().join("databases/testdb/GRCh38");
+        let link = version_dir.join("clinvar.vcf.gz.csi");
+        assert!(link.is_symlink());
+        assert_eq!(
+            fs::read_link(&link).unwrap(),
+            version_dir.join("20240115/clinvar.vcf.gz.csi")
+        );
+        assert_eq!(fs::read(&link).unwrap(), csi);
+        assert!(crate::vcf::check_index(&link).unwrap());
+        assert!(!version_dir.join("clinvar.vcf.gz.tbi").exists());
+        assert!(!version_dir.join("20240115/clinvar.vcf.gz.tbi").exists());
+    }
+
+    #[test]
+    fn tbi_is_listed_before_csi() {
+        let yaml = "clinvar:\n  GRCh38:\n    vcf: https://example.org/clinvar.vcf.gz\n    tbi: https://example.org/clinvar.vcf.gz.tbi\n    csi: https://example.org/clinvar.vcf.gz.csi\n    md5: https://example.org/clinvar.vcf.gz.md5\n";
+        let config = parse_config(yaml).unwrap();
+
+        let names: Vec<_> = config["clinvar"]["GRCh38"]
+            .indexes()
+            .into_iter()
+            .map(|(_, _, name)| name)
+            .collect();
+
+        assert_eq!(names, ["clinvar.vcf.gz.tbi", "clinvar.vcf.gz.csi"]);
+    }
+
+    #[test]
+    fn an_index_is_required() {
+        let yaml = "clinvar:\n  GRCh38:\n    vcf: https://example.org/clinvar.vcf.gz\n    md5: https://example.org/clinvar.vcf.gz.md5\n";
+
+        let error = parse_config(yaml).unwrap_err();
+
+        assert!(
+            error.to_string().contains("needs a tbi or csi index URL"),
+            "{}",
+            error
+        );
+    }
+
+    /// The release plus a `.tbi`.
+    fn indexed_release() -> Vec<(&'static str, Vec<u8>)> {
+        let mut files = release();
+        files.push((
+            "/clinvar_20240115.vcf.gz.tbi",
+            crate::bgzf::compress(b"TBI\x01", 1024),
+        ));
+        files
+    }
+
+    /// The indexed release with a sidecar holding the MD5 of the
+    /// uncompressed VCF.
+    fn release_with_decompressed_md5() -> Vec<(&'static str, Vec<u8>)> {
+        let mut files = indexed_release();
+        files[1].1 = format!("{:x}  clinvar_20240115.vcf\n", md5::compute(VCF)).into_bytes();
+        files
+    }
+
+    /// Catalog fields for the `.tbi` served alongside the release.
+    fn tbi(server: &TestServer) -> String {
+        format!("    tbi: {}\n", server.url("/clinvar_20240115.vcf.gz.tbi"))
+    }
+
+    #[tokio::test]
+    async fn decompressed_checksum_target_verifies() {
+        let server = serve(release_with_decompressed_md5()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let extra = format!("{}    checksum_target: decompressed\n", tbi(&server));
+        let manager = manager_for(&dir, &server, &extra);
+
+        manager.download_database("testdb", "GRCh38").await.unwrap();
+
+        let version_dir = dir.path().join("databases/testdb/GRCh38");
+        assert_eq!(
+            fs::read(version_dir.join("clinvar.vcf.gz")).unwrap(),
+            crate::bgzf::compress(VCF, 64 * 1024)
+        );
+        assert!(matches!(
+            manager.verify_database("testdb", "GRCh38").unwrap(),
+            VerifyStatus::Valid
+        ));
+    }
+
+    #[tokio::test]
+    async fn decompressed_checksum_fails_against_compressed_bytes() {
+        let server = serve(release_with_decompressed_md5()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let manager = manager_for(&dir, &server, &tbi(&server));
+
+        let error = manager
+            .download_database("testdb", "GRCh38")
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.kind(), "checksum", "{}", error);
+    }
+
+    #[tokio::test]
+    async fn background_checksum_mismatch_leaves_the_index_unlinked() {
+        let server = serve(release_with_decompressed_md5()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let manager = manager_for(&dir, &server, &tbi(&server));
+
+        manager
+            .download_database("testdb", "GRCh38")
+            .await
+            .unwrap_err();
+
+        let version_dir = dir.path().join("databases/testdb/GRCh38");
+        assert!(version_dir.join("20240115/clinvar.vcf.gz.tbi").exists());
+        assert!(!is_link(&version_dir.join("clinvar.vcf.gz.tbi")));
+        assert!(!is_link(&version_dir.join("clinvar.vcf.gz")));
+    }
+
+    #[tokio::test]
+    async fn signing_key_attests_a_verified_release() {
+        let server = serve(indexed_release()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let manager = manager_for(&dir, &server, &tbi(&server));
+        let pkcs8 =
+            ring::signature::Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new())
+                .unwrap();
+        let options = DownloadOptions {
+            signing_key: Some(Arc::new(SigningKey::from_pkcs8(pkcs8.as_ref()).unwrap())),
+            ..Default::default()
+        };
+
+        manager
+            .download_database_with_options("testdb", "GRCh38", &options)
+            .await
+            .unwrap();
+
+        let release_dir = dir.path().join("databases/testdb/GRCh38/20240115");
+        let envelope: crate::attestation::Envelope =
+            serde_json::from_str(&fs::read_to_string(release_dir.join(ATTESTATION_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(envelope.payload_type, crate::attestation::PAYLOAD_TYPE);
+        assert_eq!(envelope.signatures.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn touch_writes_the_manifest_of_an_adopted_release() {
+        let server = serve(indexed_release()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let manager = manager_for(&dir, &server, &tbi(&server));
+        manager.download_database("testdb", "GRCh38").await.unwrap();
+        let version_dir = dir.path().join("databases/testdb/GRCh38");
+        let adopted = version_dir.join("20240201");
+        fs::create_dir(&adopted).unwrap();
+        for name in ["clinvar.vcf.gz", "clinvar.vcf.gz.tbi", "clinvar.vcf.gz.md5"] {
+            fs::copy(version_dir.join("20240115").join(name), adopted.join(name)).unwrap();
+        }
+
+        let results = manager.touch("testdb", "GRCh38", "20240201").unwrap();
+
+        assert!(results.iter().all(|result| result.passed), "{:?}", results);
+        let manifest = manager.read_manifest("testdb", "GRCh38").unwrap();
+        assert_eq!(manifest.release, "20240201");
+        assert!(manifest.checksum_verified);
+        let names: Vec<_> = manifest.files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["clinvar.vcf.gz", "clinvar.vcf.gz.tbi", "clinvar.vcf.gz.md5"]
+        );
+        assert_eq!(
+            manifest.files[0].url,
+            server.url("/clinvar_20240115.vcf.gz")
+        );
+    }
+
+    #[tokio::test]
+    async fn current_release_follows_a_copied_link() {
+        let server = serve(indexed_release()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let manager = manager_for(&dir, &server, &tbi(&server));
+        manager.download_database("testdb", "GRCh38").await.unwrap();
+        let version_dir = dir.path().join("databases/testdb/GRCh38");
+        let link = version_dir.join("clinvar.vcf.gz");
+        let target = fs::read_link(&link).unwrap();
+        fs::remove_file(&link).unwrap();
+
+        crate::downloader::copy_as_link(&target, &version_dir.join(&target), &link).unwrap();
+
+        assert_eq!(
+            manager.current_release("testdb", "GRCh38").as_deref(),
+            Some("20240115")
+        );
+        manager.download_database("testdb", "GRCh38").await.unwrap();
+        assert!(matches!(
+            manager.verify_database("testdb", "GRCh38").unwrap(),
+            VerifyStatus::Valid
+        ));
+    }
+
+    #[tokio::test]
+    async fn verify_reports_progress_per_file() {
+        let server = serve(indexed_release()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let manager = manager_for(&dir, &server, &tbi(&server));
+        manager.download_database("testdb", "GRCh38").await.unwrap();
+
+        let mut files = Vec::new();
+        let results = manager
+            .verify_database_with_progress("testdb", "GRCh38", |file, _, _| {
+                if files.last() != Some(&file.to_string()) {
+                    files.push(file.to_string());
+                }
+                true
+            })
+            .unwrap();
+
+        assert!(results.iter().all(|result| result.passed), "{:?}", results);
+        assert_eq!(
+            files,
+            ["clinvar.vcf.gz", "clinvar.vcf.gz.tbi", "clinvar.vcf.gz.md5"]
+        );
+    }
+
+    #[tokio::test]
+    async fn verify_stops_when_progress_cancels() {
+        let server = serve(indexed_release()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let manager = manager_for(&dir, &server, &tbi(&server));
+        manager.download_database("testdb", "GRCh38").await.unwrap();
+
+        let mut calls = 0;
+        let error = manager
+            .verify_database_with_progress("testdb", "GRCh38", |_, _, _| {
+                calls += 1;
+                false
+            })
+            .unwrap_err();
+
+        assert!(error.is_cancelled(), "{}", error);
+        assert_eq!(error.code(), 9);
+        assert_eq!(calls, 1);
+    }
+
+    /// Leave what a run that crashed `written` bytes into the release's VCF
+    /// download would: its `.part` file and journal.
+    fn crash_mid_download(dir: &tempfile::TempDir, url: &str, written: usize) -> PathBuf {
+        let vcf = crate::bgzf::compress(VCF, 64 * 1024);
+        let target = dir
+            .path()
+            .join("databases/testdb/GRCh38/20240115/clinvar.vcf.gz");
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        fs::write(part_path(&target), &vcf[..written]).unwrap();
+        let md5 = format!("{:x}", md5::compute(&vcf));
+        DownloadJournal::new(url, &target, Some(&md5))
+            .save()
+            .unwrap();
+        target
+    }
+
+    #[tokio::test]
+    async fn restart_resumes_a_journaled_download() {
+        let server = serve(indexed_release()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let manager = manager_for(&dir, &server, &tbi(&server));
+        let target = crash_mid_download(&dir, &server.url("/clinvar_20240115.vcf.gz"), 20);
+
+        manager.download_database("testdb", "GRCh38").await.unwrap();
+
+        assert_eq!(
+            fs::read(&target).unwrap(),
+            crate::bgzf::compress(VCF, 64 * 1024)
+        );
+        let vcf_request = server
+            .requests()
+            .into_iter()
+            .find(|request| request.path == "/clinvar_20240115.vcf.gz")
+            .unwrap();
+        assert_eq!(vcf_request.header("range"), Some("bytes=20-"));
+        assert!(!DownloadJournal::path_for(&target).exists());
+        assert!(!part_path(&target).exists());
+    }
+
+    #[tokio::test]
+    async fn journal_for_another_url_is_discarded() {
+        let server = serve(indexed_release()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let manager = manager_for(&dir, &server, &tbi(&server));
+        let target = crash_mid_download(&dir, "https://example.org/clinvar_20231231.vcf.gz", 20);
+
+        manager.download_database("testdb", "GRCh38").await.unwrap();
+
+        assert_eq!(
+            fs::read(&target).unwrap(),
+            crate::bgzf::compress(VCF, 64 * 1024)
+        );
+        assert!(server
+            .requests()
+            .iter()
+            .all(|request| request.header("range").is_none()));
+        assert!(!DownloadJournal::path_for(&target).exists());
+    }
+
+    #[tokio::test]
+    async fn overlong_promote_delay_is_an_error() {
+        let server = serve(indexed_release()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let extra = format!("{}    promote_delay: {}\n", tbi(&server), u64::MAX);
+        let manager = manager_for(&dir, &server, &extra);
+
+        let error = manager
+            .download_database("testdb", "GRCh38")
+            .await
+            .unwrap_err();
+
+        assert!(error.to_string().contains("too long"), "{}", error);
+    }
+
+    /// Lay out a release of `db_name` whose VCF is hard-linked from the
+    /// object store, as `--content-addressed` leaves it.
+    #[cfg(unix)]
+    fn stored_release(manager: &DatabaseManager, db_name: &str, body: &[u8]) {
+        let release = manager.base_dir().join(db_name).join("GRCh38/20240115");
+        fs::create_dir_all(&release).unwrap();
+        let objects = manager.base_dir().join(OBJECTS_DIR);
+        fs::create_dir_all(&objects).unwrap();
+        let object = objects.join(format!("{:x}", md5::compute(body)));
+        if !object.exists() {
+            fs::write(&object, body).unwrap();
+        }
+        fs::hard_link(&object, release.join("clinvar.vcf.gz")).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn removing_the_last_link_collects_the_object() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = manager(&dir);
+        stored_release(&manager, "testdb", b"shared");
+
+        let freed = manager.remove_database("testdb", "GRCh38").unwrap();
+
+        assert_eq!(freed, 6);
+        let objects = manager.base_dir().join(OBJECTS_DIR);
+        assert_eq!(fs::read_dir(objects).unwrap().count(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn shared_objects_are_freed_with_their_last_release() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = manager(&dir);
+        stored_release(&manager, "testdb", b"shared");
+        stored_release(&manager, "otherdb", b"shared");
+        let object = manager
+            .base_dir()
+            .join(OBJECTS_DIR)
+            .join(format!("{:x}", md5::compute(b"shared")));
+
+        assert_eq!(manager.remove_database("testdb", "GRCh38").unwrap(), 0);
+        assert!(object.exists());
+        assert_eq!(manager.remove_database("otherdb", "GRCh38").unwrap(), 6);
+        assert!(!object.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn prune_collects_objects_of_removed_releases() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = manager(&dir);
+        stored_release(&manager, "testdb", b"old release");
+        let version_dir = manager.base_dir().join("testdb/GRCh38");
+        fs::create_dir_all(version_dir.join("20240201")).unwrap();
+        fs::write(version_dir.join("20240201/clinvar.vcf.gz"), b"new").unwrap();
+
+        let report = manager.prune("testdb", "GRCh38", 1).unwrap();
+
+        assert_eq!(report.removed, ["20240115"]);
+        assert_eq!(report.freed, 11);
+        let objects = manager.base_dir().join(OBJECTS_DIR);
+        assert_eq!(fs::read_dir(objects).unwrap().count(), 0);
     }
 }
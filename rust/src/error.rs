@@ -1,4 +1,5 @@
 use std::fmt;
+use std::path::PathBuf;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -7,6 +8,22 @@ pub enum Error {
     Io(std::io::Error),
     Network(reqwest::Error),
     Yaml(serde_yaml::Error),
+    Json(serde_json::Error),
+    /// The catalog has no database by this name.
+    DatabaseNotFound(String),
+    /// The database is in the catalog, but not for this genome version.
+    GenomeVersionNotFound {
+        database: String,
+        version: String,
+    },
+    /// A file's content doesn't match its published checksum.
+    ChecksumMismatch {
+        path: PathBuf,
+        expected: String,
+        actual: String,
+    },
+    /// The caller asked for the operation to stop before it finished.
+    Cancelled,
     Other(anyhow::Error),
 }
 
@@ -16,6 +33,27 @@ impl fmt::Display for Error {
             Error::Io(e) => write!(f, "IO error: {}", e),
             Error::Network(e) => write!(f, "Network error: {}", e),
             Error::Yaml(e) => write!(f, "YAML parsing error: {}", e),
+            Error::Json(e) => write!(f, "JSON error: {}", e),
+            Error::DatabaseNotFound(database) => {
+                write!(f, "Database '{}' not found in configuration", database)
+            }
+            Error::GenomeVersionNotFound { database, version } => write!(
+                f,
+                "Genome version '{}' not found for database '{}'",
+                version, database
+            ),
+            Error::ChecksumMismatch {
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Checksum mismatch for {}: expected {}, got {}",
+                path.display(),
+                expected,
+                actual
+            ),
+            Error::Cancelled => write!(f, "Cancelled"),
             Error::Other(e) => write!(f, "Error: {}", e),
         }
     }
@@ -41,8 +79,181 @@ impl From<serde_yaml::Error> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
 impl From<anyhow::Error> for Error {
     fn from(err: anyhow::Error) -> Self {
         Error::Other(err)
     }
 }
+
+impl Error {
+    /// Stable category of the error for machine consumers: "not_found",
+    /// "checksum", "cancelled", "network", "io", "yaml", "json" or "other".
+    /// Errors wrapped in context are classified by the innermost typed cause.
+    pub fn kind(&self) -> &'static str {
+        if self.is_not_found() {
+            "not_found"
+        } else if self.checksum_mismatch().is_some() {
+            "checksum"
+        } else if self.is_cancelled() {
+            "cancelled"
+        } else if self.find::<reqwest::Error>().is_some() {
+            "network"
+        } else if self.find::<std::io::Error>().is_some() {
+            "io"
+        } else if self.find::<serde_yaml::Error>().is_some() {
+            "yaml"
+        } else if self.find::<serde_json::Error>().is_some() {
+            "json"
+        } else {
+            "other"
+        }
+    }
+
+    /// Stable integer code for the error's `kind()`, used as the CLI exit
+    /// status and, negated, as the return value of failing C API calls.
+    ///
+    /// | code | kind        |
+    /// |------|-------------|
+    /// | 1    | `other`     |
+    /// | 3    | `not_found` |
+    /// | 4    | `network`   |
+    /// | 5    | `io`        |
+    /// | 6    | `checksum`  |
+    /// | 7    | `yaml`      |
+    /// | 8    | `json`      |
+    /// | 9    | `cancelled` |
+    ///
+    /// 2 is left out because `glade database check` exits with it to report
+    /// outdated databases.
+    pub fn code(&self) -> i32 {
+        match self.kind() {
+            "not_found" => 3,
+            "network" => 4,
+            "io" => 5,
+            "checksum" => 6,
+            "yaml" => 7,
+            "json" => 8,
+            "cancelled" => 9,
+            _ => 1,
+        }
+    }
+
+    /// The error and every cause under it, without the "Error: " prefixes.
+    pub fn message(&self) -> String {
+        match self {
+            Error::Io(e) => e.to_string(),
+            Error::Network(e) => e.to_string(),
+            Error::Yaml(e) => e.to_string(),
+            Error::Json(e) => e.to_string(),
+            Error::DatabaseNotFound(_)
+            | Error::GenomeVersionNotFound { .. }
+            | Error::ChecksumMismatch { .. }
+            | Error::Cancelled => self.to_string(),
+            Error::Other(e) => e
+                .chain()
+                .map(|cause| match cause.downcast_ref::<Error>() {
+                    Some(inner) => inner.message(),
+                    None => cause.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(": "),
+        }
+    }
+
+    /// The error as `{"error": {"kind": ..., "message": ..., "url": ...}}`,
+    /// with `url` present only for network errors that carry one, and `path`,
+    /// `expected` and `actual` for checksum mismatches.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut error = serde_json::Map::new();
+        error.insert("kind".into(), self.kind().into());
+        error.insert("code".into(), self.code().into());
+        error.insert("message".into(), self.message().into());
+        if let Some(url) = self.find::<reqwest::Error>().and_then(|e| e.url()) {
+            error.insert("url".into(), url.as_str().into());
+        }
+        if let Some(Error::ChecksumMismatch {
+            path,
+            expected,
+            actual,
+        }) = self.checksum_mismatch()
+        {
+            error.insert("path".into(), path.display().to_string().into());
+            error.insert("expected".into(), expected.as_str().into());
+            error.insert("actual".into(), actual.as_str().into());
+        }
+        serde_json::json!({ "error": error })
+    }
+
+    /// Whether this error, or one it wraps, is an unknown database or genome
+    /// version.
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            Error::DatabaseNotFound(_) | Error::GenomeVersionNotFound { .. } => true,
+            Error::Other(e) => e.chain().any(|cause| {
+                cause
+                    .downcast_ref::<Error>()
+                    .is_some_and(Error::is_not_found)
+            }),
+            _ => false,
+        }
+    }
+
+    /// Whether this error, or one it wraps, is a checksum mismatch.
+    pub fn is_checksum_mismatch(&self) -> bool {
+        self.checksum_mismatch().is_some()
+    }
+
+    /// Whether this error, or one it wraps, is a cancellation.
+    pub fn is_cancelled(&self) -> bool {
+        match self {
+            Error::Cancelled => true,
+            Error::Other(e) => e.chain().any(|cause| {
+                cause
+                    .downcast_ref::<Error>()
+                    .is_some_and(Error::is_cancelled)
+            }),
+            _ => false,
+        }
+    }
+
+    /// The `ChecksumMismatch` this error is or wraps.
+    fn checksum_mismatch(&self) -> Option<&Error> {
+        match self {
+            Error::ChecksumMismatch { .. } => Some(self),
+            Error::Other(e) => e.chain().find_map(|cause| {
+                cause
+                    .downcast_ref::<Error>()
+                    .and_then(Error::checksum_mismatch)
+            }),
+            _ => None,
+        }
+    }
+
+    fn find<T: std::error::Error + 'static>(&self) -> Option<&T> {
+        let cause: &(dyn std::error::Error + 'static) = match self {
+            Error::Io(e) => e,
+            Error::Network(e) => e,
+            Error::Yaml(e) => e,
+            Error::Json(e) => e,
+            Error::DatabaseNotFound(_)
+            | Error::GenomeVersionNotFound { .. }
+            | Error::ChecksumMismatch { .. }
+            | Error::Cancelled => return None,
+            Error::Other(e) => {
+                return e
+                    .chain()
+                    .find_map(|cause| match cause.downcast_ref::<Error>() {
+                        Some(inner) => inner.find(),
+                        None => cause.downcast_ref(),
+                    })
+            }
+        };
+        cause.downcast_ref()
+    }
+}
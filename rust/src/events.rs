@@ -0,0 +1,303 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Lifecycle events emitted while downloading and verifying databases.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum GladeEvent {
+    DownloadStarted {
+        database: String,
+        genome_version: String,
+        file: String,
+        url: String,
+    },
+    Progress {
+        url: String,
+        downloaded: u64,
+        total: Option<u64>,
+    },
+    Verified {
+        database: String,
+        genome_version: String,
+        file: String,
+        valid: bool,
+    },
+    Completed {
+        database: String,
+        genome_version: String,
+        date: String,
+        path: String,
+    },
+    Failed {
+        database: String,
+        genome_version: String,
+        error: String,
+    },
+    /// The whole run is over; `error` is set if it failed.
+    RunFinished { error: Option<String> },
+}
+
+/// Sending half of the event channel. Sending never blocks and is a no-op
+/// when nobody is subscribed.
+#[derive(Debug, Clone)]
+pub struct EventSender {
+    sender: broadcast::Sender<GladeEvent>,
+}
+
+impl Default for EventSender {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+}
+
+impl EventSender {
+    pub fn emit(&self, event: GladeEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<GladeEvent> {
+        self.sender.subscribe()
+    }
+}
+
+/// Forward events as newline-delimited JSON over a Unix domain socket until
+/// the channel closes. Connects to a listener at `path` if one exists,
+/// otherwise listens there and streams to every client that connects.
+/// Consumers that disconnect are dropped; they never fail a download.
+#[cfg(unix)]
+pub fn spawn_socket_forwarder(
+    path: PathBuf,
+    mut receiver: broadcast::Receiver<GladeEvent>,
+) -> std::io::Result<JoinHandle<()>> {
+    use std::sync::Arc;
+    use tokio::net::{UnixListener, UnixStream};
+    use tokio::sync::Mutex;
+
+    let clients: Arc<Mutex<Vec<UnixStream>>> = Arc::new(Mutex::new(Vec::new()));
+
+    let connected = std::os::unix::net::UnixStream::connect(&path).and_then(|stream| {
+        stream.set_nonblocking(true)?;
+        UnixStream::from_std(stream)
+    });
+    let listener = match connected {
+        Ok(stream) => {
+            clients
+                .try_lock()
+                .expect("no other holders yet")
+                .push(stream);
+            None
+        }
+        Err(_) => {
+            if std::fs::symlink_metadata(&path).is_ok() {
+                std::fs::remove_file(&path)?;
+            }
+            let listener = UnixListener::bind(&path)?;
+            let accepted = clients.clone();
+            Some(tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    accepted.lock().await.push(stream);
+                }
+            }))
+        }
+    };
+
+    Ok(tokio::spawn(async move {
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let Ok(mut line) = serde_json::to_vec(&event) else {
+                continue;
+            };
+            line.push(b'\n');
+
+            let mut clients = clients.lock().await;
+            let mut live = Vec::with_capacity(clients.len());
+            for mut stream in clients.drain(..) {
+                if stream.write_all(&line).await.is_ok() {
+                    live.push(stream);
+                }
+            }
+            *clients = live;
+        }
+
+        if let Some(listener) = listener {
+            listener.abort();
+            let _ = std::fs::remove_file(&path);
+        }
+    }))
+}
+
+/// Bytes between progress lines in the event log when the total size isn't
+/// known.
+const LOG_PROGRESS_STEP: u64 = 100 * 1024 * 1024;
+
+/// Append events as newline-delimited JSON to a file until the channel
+/// closes, each stamped with a `time` field and flushed as it's written so
+/// `tail -f` sees it live. Progress is thinned to every 10% (or 100 MiB when
+/// the size is unknown).
+pub fn spawn_file_forwarder(
+    path: PathBuf,
+    mut receiver: broadcast::Receiver<GladeEvent>,
+) -> std::io::Result<JoinHandle<()>> {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    let mut file = tokio::fs::File::from_std(file);
+
+    Ok(tokio::spawn(async move {
+        // Last progress step logged for each URL.
+        let mut milestones: HashMap<String, u64> = HashMap::new();
+
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+
+            if let GladeEvent::Progress {
+                url,
+                downloaded,
+                total,
+            } = &event
+            {
+                let step = match total {
+                    Some(total) if *total > 0 => downloaded * 10 / total,
+                    _ => downloaded / LOG_PROGRESS_STEP,
+                };
+                if milestones.insert(url.clone(), step) == Some(step) {
+                    continue;
+                }
+            }
+
+            let Ok(serde_json::Value::Object(mut record)) = serde_json::to_value(&event) else {
+                continue;
+            };
+            record.insert("time".to_string(), chrono::Utc::now().to_rfc3339().into());
+            let Ok(mut line) = serde_json::to_vec(&record) else {
+                continue;
+            };
+            line.push(b'\n');
+            if file.write_all(&line).await.is_err() || file.flush().await.is_err() {
+                break;
+            }
+        }
+    }))
+}
+
+/// Where `spawn_syslog_forwarder` delivers events.
+#[derive(Debug, Clone)]
+pub enum SyslogTarget {
+    /// The local syslog daemon's `/dev/log` socket.
+    #[cfg(unix)]
+    Local,
+    /// A remote collector over UDP, as `host` or `host:port` (port 514 by
+    /// default).
+    Remote(String),
+}
+
+impl GladeEvent {
+    /// Syslog severity: errors for failures and checksum mismatches so they
+    /// trip alerts, `None` for progress, which is too chatty to forward.
+    fn syslog_severity(&self) -> Option<u8> {
+        match self {
+            GladeEvent::Failed { .. } => Some(3),
+            GladeEvent::Verified { valid: false, .. } => Some(3),
+            GladeEvent::RunFinished { error: Some(_) } => Some(3),
+            GladeEvent::Completed { .. } | GladeEvent::RunFinished { .. } => Some(5),
+            GladeEvent::Verified { .. } | GladeEvent::DownloadStarted { .. } => Some(6),
+            GladeEvent::Progress { .. } => None,
+        }
+    }
+}
+
+enum SyslogSocket {
+    #[cfg(unix)]
+    Local(std::os::unix::net::UnixDatagram),
+    Remote(std::net::UdpSocket),
+}
+
+impl SyslogSocket {
+    fn send(&self, message: &[u8]) {
+        let _ = match self {
+            #[cfg(unix)]
+            SyslogSocket::Local(socket) => socket.send(message),
+            SyslogSocket::Remote(socket) => socket.send(message),
+        };
+    }
+}
+
+/// Forward events to syslog as RFC 5424 messages with the JSON event as the
+/// message body, until the channel closes. Delivery is best effort; a
+/// collector that's down never fails a download.
+pub fn spawn_syslog_forwarder(
+    target: SyslogTarget,
+    mut receiver: broadcast::Receiver<GladeEvent>,
+) -> std::io::Result<JoinHandle<()>> {
+    // Facility "user".
+    const FACILITY: u8 = 1;
+
+    let socket = match target {
+        #[cfg(unix)]
+        SyslogTarget::Local => {
+            let socket = std::os::unix::net::UnixDatagram::unbound()?;
+            socket.connect("/dev/log")?;
+            SyslogSocket::Local(socket)
+        }
+        SyslogTarget::Remote(host) => {
+            let address = if host.contains(':') {
+                host
+            } else {
+                format!("{}:514", host)
+            };
+            let socket = std::net::UdpSocket::bind("0.0.0.0:0")?;
+            socket.connect(address)?;
+            SyslogSocket::Remote(socket)
+        }
+    };
+
+    let hostname = std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "-".to_string());
+    let pid = std::process::id();
+
+    Ok(tokio::spawn(async move {
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let Some(severity) = event.syslog_severity() else {
+                continue;
+            };
+            let Ok(body) = serde_json::to_string(&event) else {
+                continue;
+            };
+
+            let message = format!(
+                "<{}>1 {} {} glade {} - - {}",
+                FACILITY * 8 + severity,
+                chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                hostname,
+                pid,
+                body
+            );
+            socket.send(message.as_bytes());
+        }
+    }))
+}
@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+use crate::Result;
+
+/// Politeness limits a mirror asks clients to respect, applied per host.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct HostLimits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_second: Option<f64>,
+}
+
+impl HostLimits {
+    pub fn validate(&self) -> Result<()> {
+        if self.max_concurrent == Some(0) {
+            return Err(anyhow::anyhow!("max_concurrent must be at least 1").into());
+        }
+
+        if let Some(rps) = self.requests_per_second {
+            if !rps.is_finite() || rps <= 0.0 {
+                return Err(anyhow::anyhow!("requests_per_second must be positive").into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct HostLimiter {
+    limits: HostLimits,
+    semaphore: Option<Arc<Semaphore>>,
+    next_slot: tokio::sync::Mutex<Instant>,
+}
+
+impl HostLimiter {
+    fn new(limits: HostLimits) -> Self {
+        Self {
+            limits,
+            semaphore: limits.max_concurrent.map(|n| Arc::new(Semaphore::new(n))),
+            next_slot: tokio::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    async fn acquire(&self) -> HostPermit {
+        let permit = match &self.semaphore {
+            Some(semaphore) => semaphore.clone().acquire_owned().await.ok(),
+            None => None,
+        };
+
+        if let Some(rps) = self.limits.requests_per_second {
+            let mut next_slot = self.next_slot.lock().await;
+            tokio::time::sleep_until(*next_slot).await;
+            *next_slot = Instant::now() + Duration::from_secs_f64(1.0 / rps);
+        }
+
+        HostPermit { _permit: permit }
+    }
+}
+
+/// Held for the lifetime of a request; releases the host's concurrency slot
+/// when dropped.
+pub struct HostPermit {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+/// Per-host concurrency and request-rate limiters.
+#[derive(Default)]
+pub struct HostLimiters {
+    hosts: Mutex<HashMap<String, Arc<HostLimiter>>>,
+}
+
+impl HostLimiters {
+    pub fn set(&self, host: &str, limits: HostLimits) -> Result<()> {
+        limits.validate()?;
+
+        let mut hosts = self.hosts.lock().unwrap();
+        // Keep an existing limiter with the same limits so in-flight permits
+        // and the rate window still count against it.
+        if hosts
+            .get(host)
+            .is_none_or(|limiter| limiter.limits != limits)
+        {
+            hosts.insert(host.to_string(), Arc::new(HostLimiter::new(limits)));
+        }
+
+        Ok(())
+    }
+
+    pub async fn acquire(&self, host: &str) -> HostPermit {
+        let limiter = self.hosts.lock().unwrap().get(host).cloned();
+
+        match limiter {
+            Some(limiter) => limiter.acquire().await,
+            None => HostPermit { _permit: None },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Run `tasks` requests to `host` at once and return the most that held
+    /// a permit simultaneously.
+    async fn peak_concurrency(
+        limiters: Arc<HostLimiters>,
+        host: &'static str,
+        tasks: usize,
+    ) -> usize {
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..tasks)
+            .map(|_| {
+                let (limiters, active, peak) = (limiters.clone(), active.clone(), peak.clone());
+                tokio::spawn(async move {
+                    let _permit = limiters.acquire(host).await;
+                    let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    active.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        peak.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test]
+    async fn max_concurrent_caps_requests_per_host() {
+        let limiters = Arc::new(HostLimiters::default());
+        let limits = HostLimits {
+            max_concurrent: Some(2),
+            requests_per_second: None,
+        };
+        limiters.set("ftp.ncbi.nlm.nih.gov", limits).unwrap();
+
+        assert_eq!(
+            peak_concurrency(limiters, "ftp.ncbi.nlm.nih.gov", 8).await,
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn limits_apply_only_to_their_host() {
+        let limiters = Arc::new(HostLimiters::default());
+        let limits = HostLimits {
+            max_concurrent: Some(1),
+            requests_per_second: None,
+        };
+        limiters.set("ftp.ncbi.nlm.nih.gov", limits).unwrap();
+
+        assert_eq!(peak_concurrency(limiters, "ftp.ebi.ac.uk", 4).await, 4);
+    }
+
+    #[tokio::test]
+    async fn requests_per_second_spaces_out_requests() {
+        let limiters = HostLimiters::default();
+        let limits = HostLimits {
+            max_concurrent: None,
+            requests_per_second: Some(20.0),
+        };
+        limiters.set("ftp.ebi.ac.uk", limits).unwrap();
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiters.acquire("ftp.ebi.ac.uk").await;
+        }
+
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn rejects_zero_limits() {
+        let zero_concurrency = HostLimits {
+            max_concurrent: Some(0),
+            requests_per_second: None,
+        };
+        let zero_rate = HostLimits {
+            max_concurrent: None,
+            requests_per_second: Some(0.0),
+        };
+
+        assert!(zero_concurrency.validate().is_err());
+        assert!(zero_rate.validate().is_err());
+    }
+}
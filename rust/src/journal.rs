@@ -0,0 +1,103 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::downloader::part_path;
+use crate::Result;
+
+/// Sidecar written next to a file while it downloads and removed once it's
+/// complete, so a later run can tell that a crash interrupted the download
+/// and resume its `.part` file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DownloadJournal {
+    pub url: String,
+    pub target: PathBuf,
+    /// Read from `expected_md5` in journals written before it could hold
+    /// other algorithms' digests.
+    #[serde(
+        default,
+        alias = "expected_md5",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub expected_checksum: Option<String>,
+    /// Bytes in the `.part` file as of the last attempt; it may have grown
+    /// since if the process died mid-stream.
+    pub bytes_written: u64,
+}
+
+impl DownloadJournal {
+    pub fn new(url: &str, target: &Path, expected_checksum: Option<&str>) -> Self {
+        Self {
+            url: url.to_string(),
+            target: target.to_path_buf(),
+            expected_checksum: expected_checksum.map(str::to_string),
+            bytes_written: written(target),
+        }
+    }
+
+    pub fn path_for(target: &Path) -> PathBuf {
+        let mut path = target.as_os_str().to_owned();
+        path.push(".journal");
+        PathBuf::from(path)
+    }
+
+    /// The journal of an interrupted download of `target`, if there is one.
+    /// An unreadable journal counts as one with nothing worth resuming.
+    pub fn load(target: &Path) -> Option<Self> {
+        let content = fs::read_to_string(Self::path_for(target)).ok()?;
+        Some(serde_json::from_str(&content).unwrap_or_else(|_| Self::new("", target, None)))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::write(
+            Self::path_for(&self.target),
+            serde_json::to_string_pretty(self)?,
+        )
+        .context("Failed to write download journal")?;
+        Ok(())
+    }
+
+    /// Record how far the download got before failing.
+    pub fn checkpoint(&mut self) -> Result<()> {
+        self.bytes_written = written(&self.target);
+        self.save()
+    }
+
+    pub fn remove(target: &Path) -> Result<()> {
+        match fs::remove_file(Self::path_for(target)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(anyhow::Error::new(e)
+                .context("Failed to remove download journal")
+                .into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn written(target: &Path) -> u64 {
+    fs::metadata(part_path(target)).map_or(0, |metadata| metadata.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_journals_written_with_expected_md5() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("clinvar.vcf.gz");
+        fs::write(
+            DownloadJournal::path_for(&target),
+            r#"{"url": "https://example.org/clinvar.vcf.gz", "target": "clinvar.vcf.gz", "expected_md5": "5d41402abc4b2a76b9719d911017c592", "bytes_written": 5}"#,
+        )
+        .unwrap();
+
+        let journal = DownloadJournal::load(&target).unwrap();
+
+        assert_eq!(
+            journal.expected_checksum.as_deref(),
+            Some("5d41402abc4b2a76b9719d911017c592")
+        );
+        assert_eq!(journal.bytes_written, 5);
+    }
+}
@@ -1,10 +1,83 @@
+pub mod attestation;
+pub mod bgzf;
+pub mod budget;
+pub mod checksum;
 pub mod config;
 pub mod database;
 pub mod downloader;
 pub mod error;
+pub mod events;
+pub mod host_limits;
+pub mod journal;
+pub mod metered;
+pub mod report;
+pub mod throttle;
+pub mod vcf;
+
+#[cfg(feature = "python")]
+mod python;
+#[cfg(test)]
+mod test_server;
 
 pub use database::DatabaseManager;
 pub use error::{Error, Result};
+pub use events::GladeEvent;
+
+thread_local! {
+    static LAST_ERROR: std::cell::RefCell<Option<(std::ffi::CString, std::os::raw::c_int)>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// Record why an FFI call failed that has no `Error` of its own, such as an
+/// invalid argument, for `glade_last_error_message()`. Its code is 1.
+fn set_last_error(message: impl std::fmt::Display) {
+    set_last_error_with_code(message, 1);
+}
+
+/// Record a failed call's error and return its negated `Error::code()`, the
+/// value integer-returning functions hand back.
+fn fail(error: Error) -> std::os::raw::c_int {
+    let code = error.code();
+    set_last_error_with_code(error, code);
+    -code
+}
+
+fn set_last_error_with_code(message: impl std::fmt::Display, code: std::os::raw::c_int) {
+    let message = message.to_string().replace('\0', "");
+    let message = std::ffi::CString::new(message).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some((message, code)));
+}
+
+/// The message of the last failed glade call on this thread, or null if none
+/// has failed since the last `glade_clear_error()`. Successful calls leave it
+/// unchanged.
+///
+/// The string is owned by glade and stays valid until the next failing call
+/// or `glade_clear_error()` on the same thread. Copy it to keep it longer;
+/// never free it.
+#[no_mangle]
+pub extern "C" fn glade_last_error_message() -> *const std::os::raw::c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |(message, _)| message.as_ptr())
+    })
+}
+
+/// The `Error::code()` of the last failed glade call on this thread, or 0 if
+/// none has failed since the last `glade_clear_error()`. Functions returning
+/// a pointer report failure as null; this tells their failures apart the way
+/// integer-returning functions do with their negated code.
+#[no_mangle]
+pub extern "C" fn glade_last_error_code() -> std::os::raw::c_int {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(0, |(_, code)| *code))
+}
+
+/// Forget the last error on this thread.
+#[no_mangle]
+pub extern "C" fn glade_clear_error() {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+}
 
 #[repr(C)]
 pub struct GladeDatabase {
@@ -18,7 +91,10 @@ pub extern "C" fn glade_new() -> *mut GladeDatabase {
             let db = Box::new(GladeDatabase { manager });
             Box::into_raw(db)
         }
-        Err(_) => std::ptr::null_mut(),
+        Err(e) => {
+            fail(e);
+            std::ptr::null_mut()
+        }
     }
 }
 
@@ -39,6 +115,11 @@ pub unsafe extern "C" fn glade_free(ptr: *mut GladeDatabase) {
 
 /// Download a database with the specified name and genome version.
 ///
+/// Returns 0 on success, or the negated `Error::code()` of the failure: -3
+/// for an unknown database or genome version, -4 network, -5 I/O, -6 checksum
+/// mismatch, -7 YAML, -8 JSON and -1 for anything else, including invalid
+/// arguments.
+///
 /// # Safety
 ///
 /// The caller must ensure that:
@@ -51,30 +132,330 @@ pub unsafe extern "C" fn glade_download_database(
     ptr: *mut GladeDatabase,
     db_name: *const std::os::raw::c_char,
     genome_version: *const std::os::raw::c_char,
+) -> std::os::raw::c_int {
+    glade_download_database_with_progress(ptr, db_name, genome_version, None, std::ptr::null_mut())
+}
+
+/// Called with the bytes of the current file downloaded so far and its size,
+/// or 0 when the size is unknown.
+pub type GladeDownloadProgressCallback =
+    extern "C" fn(downloaded: u64, total: u64, user_data: *mut std::os::raw::c_void);
+
+/// `user_data` for a callback run by the downloader.
+struct CallbackData(*mut std::os::raw::c_void);
+
+// SAFETY: the caller of `glade_download_database_with_progress` vouches that
+// `user_data` may be used from whichever thread runs the callback.
+unsafe impl Send for CallbackData {}
+unsafe impl Sync for CallbackData {}
+
+impl CallbackData {
+    fn get(&self) -> *mut std::os::raw::c_void {
+        self.0
+    }
+}
+
+/// Download a database like `glade_download_database()`, reporting each
+/// file's progress through `on_progress` instead of drawing a progress bar.
+/// A null callback behaves exactly like `glade_download_database()`, and the
+/// return codes are the same.
+///
+/// # Safety
+///
+/// The caller must ensure that:
+/// - `ptr` is a valid pointer created by `glade_new()`
+/// - `db_name` is a valid null-terminated C string
+/// - `genome_version` is a valid null-terminated C string
+/// - `user_data` is valid for whatever the callback does with it, from any
+///   thread, until the call returns
+/// - No other call uses `ptr` concurrently
+/// - All pointers remain valid for the duration of the call
+#[no_mangle]
+pub unsafe extern "C" fn glade_download_database_with_progress(
+    ptr: *mut GladeDatabase,
+    db_name: *const std::os::raw::c_char,
+    genome_version: *const std::os::raw::c_char,
+    on_progress: Option<GladeDownloadProgressCallback>,
+    user_data: *mut std::os::raw::c_void,
 ) -> std::os::raw::c_int {
     if ptr.is_null() || db_name.is_null() || genome_version.is_null() {
+        set_last_error("Null argument");
         return -1;
     }
 
     let db_name_str = match std::ffi::CStr::from_ptr(db_name).to_str() {
         Ok(s) => s,
-        Err(_) => return -1,
+        Err(_) => {
+            set_last_error("Database name is not valid UTF-8");
+            return -1;
+        }
     };
 
     let genome_version_str = match std::ffi::CStr::from_ptr(genome_version).to_str() {
         Ok(s) => s,
-        Err(_) => return -1,
+        Err(_) => {
+            set_last_error("Genome version is not valid UTF-8");
+            return -1;
+        }
     };
 
-    let database = &(*ptr).manager;
+    let database = &mut (*ptr).manager;
 
     let runtime = match tokio::runtime::Runtime::new() {
         Ok(rt) => rt,
-        Err(_) => return -1,
+        Err(e) => return fail(Error::from(e)),
     };
 
-    match runtime.block_on(database.download_database(db_name_str, genome_version_str)) {
+    if let Some(callback) = on_progress {
+        let user_data = CallbackData(user_data);
+        database.set_progress_callback(Some(std::sync::Arc::new(move |downloaded, total| {
+            callback(downloaded, total.unwrap_or(0), user_data.get())
+        })));
+    }
+    let result = runtime.block_on(database.download_database(db_name_str, genome_version_str));
+    if on_progress.is_some() {
+        database.set_progress_callback(None);
+    }
+
+    match result {
         Ok(_) => 0,
-        Err(_) => -1,
+        Err(e) => fail(e),
+    }
+}
+
+/// List every database in the catalog with its URLs and local state, as a
+/// JSON array of the objects returned by `DatabaseManager::databases_status`.
+/// Returns null on error, with its code in `glade_last_error_code()`.
+///
+/// # Safety
+///
+/// The caller must ensure that:
+/// - `ptr` is a valid pointer created by `glade_new()`
+///
+/// The returned string is owned by the caller and must be released with
+/// `glade_string_free()`, exactly once. It must not be freed with `free()` or
+/// modified.
+#[no_mangle]
+pub unsafe extern "C" fn glade_list_databases(
+    ptr: *mut GladeDatabase,
+) -> *mut std::os::raw::c_char {
+    if ptr.is_null() {
+        set_last_error("Null argument");
+        return std::ptr::null_mut();
+    }
+
+    let database = &(*ptr).manager;
+
+    let json = match database
+        .databases_status()
+        .and_then(|statuses| Ok(serde_json::to_string(&statuses)?))
+    {
+        Ok(json) => json,
+        Err(e) => {
+            fail(e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    match std::ffi::CString::new(json) {
+        Ok(json) => json.into_raw(),
+        Err(e) => {
+            set_last_error(e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// The resolved path of a downloaded database's current VCF, or null on
+/// error (including when it isn't downloaded), with its code in
+/// `glade_last_error_code()`.
+///
+/// # Safety
+///
+/// The caller must ensure that:
+/// - `ptr` is a valid pointer created by `glade_new()`
+/// - `db_name` is a valid null-terminated C string
+/// - `genome_version` is a valid null-terminated C string
+///
+/// The returned string is owned by the caller and must be released with
+/// `glade_string_free()`, exactly once.
+#[no_mangle]
+pub unsafe extern "C" fn glade_database_path(
+    ptr: *mut GladeDatabase,
+    db_name: *const std::os::raw::c_char,
+    genome_version: *const std::os::raw::c_char,
+) -> *mut std::os::raw::c_char {
+    if ptr.is_null() || db_name.is_null() || genome_version.is_null() {
+        set_last_error("Null argument");
+        return std::ptr::null_mut();
+    }
+
+    let db_name_str = match std::ffi::CStr::from_ptr(db_name).to_str() {
+        Ok(s) => s,
+        Err(_) => {
+            set_last_error("Database name is not valid UTF-8");
+            return std::ptr::null_mut();
+        }
+    };
+
+    let genome_version_str = match std::ffi::CStr::from_ptr(genome_version).to_str() {
+        Ok(s) => s,
+        Err(_) => {
+            set_last_error("Genome version is not valid UTF-8");
+            return std::ptr::null_mut();
+        }
+    };
+
+    let database = &(*ptr).manager;
+
+    let path = match database.database_path(db_name_str, genome_version_str) {
+        Ok(path) => path,
+        Err(e) => {
+            fail(e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    // Unix paths are bytes and needn't be UTF-8.
+    #[cfg(unix)]
+    let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec();
+    #[cfg(not(unix))]
+    let bytes = match path.to_str() {
+        Some(path) => path.as_bytes().to_vec(),
+        None => {
+            set_last_error("Database path is not valid UTF-8");
+            return std::ptr::null_mut();
+        }
+    };
+
+    match std::ffi::CString::new(bytes) {
+        Ok(path) => path.into_raw(),
+        Err(e) => {
+            set_last_error(e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Free a string returned by glade. Null is ignored.
+///
+/// # Safety
+///
+/// The caller must ensure that:
+/// - `s` was returned by a glade function documented as returning an owned
+///   string, such as `glade_list_databases()` or `glade_database_path()`
+/// - `s` has not been freed already, and is not used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn glade_string_free(s: *mut std::os::raw::c_char) {
+    if !s.is_null() {
+        let _ = std::ffi::CString::from_raw(s);
+    }
+}
+
+/// Called with the file being verified, the bytes checked so far and the
+/// file's size. Return 0 to carry on, or non-zero to cancel.
+pub type GladeProgressCallback = extern "C" fn(
+    file: *const std::os::raw::c_char,
+    done: u64,
+    total: u64,
+    user_data: *mut std::os::raw::c_void,
+) -> std::os::raw::c_int;
+
+/// Called once per file with whether it passed verification.
+pub type GladeVerifyResultCallback = extern "C" fn(
+    file: *const std::os::raw::c_char,
+    passed: std::os::raw::c_int,
+    user_data: *mut std::os::raw::c_void,
+);
+
+/// Verify the installed files of a database on disk, reporting hashing
+/// progress and a pass/fail result per file through the callbacks (either may
+/// be null). Returns the number of files that failed, or on error the negated
+/// `Error::code()` as for `glade_download_database()`. If the progress
+/// callback returns non-zero, verification stops and -9 (`cancelled`) is
+/// returned without calling the result callback.
+///
+/// # Safety
+///
+/// The caller must ensure that:
+/// - `ptr` is a valid pointer created by `glade_new()`
+/// - `db_name` is a valid null-terminated C string
+/// - `genome_version` is a valid null-terminated C string
+/// - `user_data` is valid for whatever the callbacks do with it
+/// - All pointers remain valid for the duration of the call
+#[no_mangle]
+pub unsafe extern "C" fn glade_verify_database_with_progress(
+    ptr: *mut GladeDatabase,
+    db_name: *const std::os::raw::c_char,
+    genome_version: *const std::os::raw::c_char,
+    on_progress: Option<GladeProgressCallback>,
+    on_result: Option<GladeVerifyResultCallback>,
+    user_data: *mut std::os::raw::c_void,
+) -> std::os::raw::c_int {
+    if ptr.is_null() || db_name.is_null() || genome_version.is_null() {
+        set_last_error("Null argument");
+        return -1;
+    }
+
+    let db_name_str = match std::ffi::CStr::from_ptr(db_name).to_str() {
+        Ok(s) => s,
+        Err(_) => {
+            set_last_error("Database name is not valid UTF-8");
+            return -1;
+        }
+    };
+
+    let genome_version_str = match std::ffi::CStr::from_ptr(genome_version).to_str() {
+        Ok(s) => s,
+        Err(_) => {
+            set_last_error("Genome version is not valid UTF-8");
+            return -1;
+        }
+    };
+
+    let database = &(*ptr).manager;
+
+    let mut current: Option<(String, std::ffi::CString)> = None;
+    let results = database.verify_database_with_progress(
+        db_name_str,
+        genome_version_str,
+        |file, done, total| {
+            let Some(callback) = on_progress else {
+                return true;
+            };
+            if current.as_ref().map(|(name, _)| name.as_str()) != Some(file) {
+                let Ok(c_file) = std::ffi::CString::new(file) else {
+                    return true;
+                };
+                current = Some((file.to_string(), c_file));
+            }
+            match &current {
+                Some((_, c_file)) => callback(c_file.as_ptr(), done, total, user_data) == 0,
+                None => true,
+            }
+        },
+    );
+
+    let results = match results {
+        Ok(results) => results,
+        Err(e) => return fail(e),
+    };
+
+    let mut failed = 0;
+    for result in &results {
+        if !result.passed {
+            failed += 1;
+        }
+        if let (Some(callback), Ok(c_file)) =
+            (on_result, std::ffi::CString::new(result.file.as_str()))
+        {
+            callback(
+                c_file.as_ptr(),
+                result.passed as std::os::raw::c_int,
+                user_data,
+            );
+        }
     }
+
+    failed
 }
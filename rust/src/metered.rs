@@ -0,0 +1,105 @@
+//! Detection of metered network connections.
+//!
+//! Supported platforms:
+//! - Linux with NetworkManager, queried over D-Bus through `busctl`.
+//! - Windows 8 and later, from the cost of the internet connection profile,
+//!   queried through PowerShell.
+//!
+//! macOS is not supported: it only reports whether a path is expensive to
+//! apps using the Network framework, with no command-line equivalent.
+//! There, elsewhere, or when the status can't be determined, the connection
+//! is treated as unmetered.
+
+/// Whether the active network connection is metered, or `None` if unknown.
+pub fn is_metered() -> Option<bool> {
+    platform::is_metered()
+}
+
+/// Interpret a Windows `NetworkCostType`: `Fixed` and `Variable` plans are
+/// metered, `Unrestricted` is not, and `Unknown` says nothing.
+#[cfg(any(windows, test))]
+fn windows_cost_is_metered(cost_type: &str) -> Option<bool> {
+    match cost_type.trim() {
+        "Fixed" | "Variable" => Some(true),
+        "Unrestricted" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::process::Command;
+
+    // NMMetered: 0 unknown, 1 yes, 2 no, 3 guess-yes, 4 guess-no.
+    pub fn is_metered() -> Option<bool> {
+        let output = Command::new("busctl")
+            .args([
+                "--system",
+                "get-property",
+                "org.freedesktop.NetworkManager",
+                "/org/freedesktop/NetworkManager",
+                "org.freedesktop.NetworkManager",
+                "Metered",
+            ])
+            .output()
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        // Output looks like "u 4".
+        let value: u32 = String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .nth(1)?
+            .parse()
+            .ok()?;
+
+        match value {
+            1 | 3 => Some(true),
+            2 | 4 => Some(false),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::process::Command;
+
+    const QUERY: &str = "[Windows.Networking.Connectivity.NetworkInformation,Windows.Networking.Connectivity,ContentType=WindowsRuntime]::GetInternetConnectionProfile().GetConnectionCost().NetworkCostType";
+
+    pub fn is_metered() -> Option<bool> {
+        let output = Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", QUERY])
+            .output()
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        super::windows_cost_is_metered(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+mod platform {
+    pub fn is_metered() -> Option<bool> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_cost_types() {
+        assert_eq!(windows_cost_is_metered("Fixed\r\n"), Some(true));
+        assert_eq!(windows_cost_is_metered("Variable"), Some(true));
+        assert_eq!(windows_cost_is_metered("Unrestricted\r\n"), Some(false));
+        assert_eq!(windows_cost_is_metered("Unknown"), None);
+        assert_eq!(windows_cost_is_metered(""), None);
+    }
+}
@@ -0,0 +1,114 @@
+//! Python bindings, built with the `python` feature:
+//!
+//! ```python
+//! import glade
+//!
+//! db = glade.GladeDatabase()
+//! db.download("clinvar", "GRCh38")
+//! print(db.path("clinvar", "GRCh38"))
+//! ```
+
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyList, PyString};
+use std::path::PathBuf;
+
+use crate::{DatabaseManager, Error};
+
+fn to_py_err(e: Error) -> PyErr {
+    PyRuntimeError::new_err(e.message())
+}
+
+/// Convert a JSON value to the equivalent Python object.
+fn to_python(py: Python<'_>, value: &serde_json::Value) -> PyResult<PyObject> {
+    use serde_json::Value;
+
+    Ok(match value {
+        Value::Null => py.None(),
+        Value::Bool(b) => PyBool::new(py, *b).to_owned().into_any().unbind(),
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(n), _) => n.into_pyobject(py)?.into_any().unbind(),
+            (_, Some(n)) => n.into_pyobject(py)?.into_any().unbind(),
+            _ => n
+                .as_f64()
+                .unwrap_or(f64::NAN)
+                .into_pyobject(py)?
+                .into_any()
+                .unbind(),
+        },
+        Value::String(s) => PyString::new(py, s).into_any().unbind(),
+        Value::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(to_python(py, item)?)?;
+            }
+            list.into_any().unbind()
+        }
+        Value::Object(fields) => {
+            let dict = PyDict::new(py);
+            for (key, value) in fields {
+                dict.set_item(key, to_python(py, value)?)?;
+            }
+            dict.into_any().unbind()
+        }
+    })
+}
+
+/// A database manager. Downloads run on an internal tokio runtime with the
+/// GIL released.
+#[pyclass(name = "GladeDatabase")]
+struct PyGladeDatabase {
+    manager: DatabaseManager,
+    runtime: tokio::runtime::Runtime,
+}
+
+#[pymethods]
+impl PyGladeDatabase {
+    /// Keep databases under `base_dir`, or `$GLADE_HOME/databases` by
+    /// default.
+    #[new]
+    #[pyo3(signature = (base_dir=None))]
+    fn new(base_dir: Option<PathBuf>) -> PyResult<Self> {
+        let manager = match base_dir {
+            Some(base_dir) => DatabaseManager::new_in(base_dir),
+            None => DatabaseManager::new(),
+        }
+        .map_err(to_py_err)?;
+        let runtime = tokio::runtime::Runtime::new().map_err(|e| to_py_err(e.into()))?;
+        Ok(Self { manager, runtime })
+    }
+
+    /// Download a database, skipping files that are already present and valid.
+    fn download(&self, py: Python<'_>, db_name: &str, genome_version: &str) -> PyResult<()> {
+        py.allow_threads(|| {
+            self.runtime
+                .block_on(self.manager.download_database(db_name, genome_version))
+        })
+        .map_err(to_py_err)
+    }
+
+    /// Download every database in the catalog.
+    fn download_all(&self, py: Python<'_>) -> PyResult<()> {
+        py.allow_threads(|| self.runtime.block_on(self.manager.download_all_databases()))
+            .map_err(to_py_err)
+    }
+
+    /// Every catalog entry with its URLs and local state, as dicts.
+    fn list(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let statuses = self.manager.databases_status().map_err(to_py_err)?;
+        let value = serde_json::to_value(statuses).map_err(|e| to_py_err(e.into()))?;
+        to_python(py, &value)
+    }
+
+    /// The resolved path of a database's current VCF.
+    fn path(&self, db_name: &str, genome_version: &str) -> PyResult<PathBuf> {
+        self.manager
+            .database_path(db_name, genome_version)
+            .map_err(to_py_err)
+    }
+}
+
+#[pymodule]
+fn glade(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyGladeDatabase>()
+}
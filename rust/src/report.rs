@@ -0,0 +1,50 @@
+use std::fmt;
+use std::io::Write;
+
+/// Where `DatabaseManager` sends its human-facing status output: banners,
+/// per-file checkmarks and summaries. Errors are returned, not reported.
+pub trait Reporter: Send + Sync {
+    /// A complete line.
+    fn line(&self, message: fmt::Arguments);
+
+    /// The start of a line that the next `line` call finishes, e.g.
+    /// "Verifying... " before "✓ Valid".
+    fn partial(&self, message: fmt::Arguments);
+}
+
+/// Prints to stdout; the default.
+pub struct StdoutReporter;
+
+impl Reporter for StdoutReporter {
+    fn line(&self, message: fmt::Arguments) {
+        println!("{}", message);
+    }
+
+    fn partial(&self, message: fmt::Arguments) {
+        print!("{}", message);
+        let _ = std::io::stdout().flush();
+    }
+}
+
+/// Prints to stderr, keeping stdout free for data, e.g. with `--stdout`.
+pub struct StderrReporter;
+
+impl Reporter for StderrReporter {
+    fn line(&self, message: fmt::Arguments) {
+        eprintln!("{}", message);
+    }
+
+    fn partial(&self, message: fmt::Arguments) {
+        eprint!("{}", message);
+        let _ = std::io::stderr().flush();
+    }
+}
+
+/// Discards everything, for `--quiet`.
+pub struct QuietReporter;
+
+impl Reporter for QuietReporter {
+    fn line(&self, _message: fmt::Arguments) {}
+
+    fn partial(&self, _message: fmt::Arguments) {}
+}
@@ -0,0 +1,38 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Caps download throughput, shared by every stream and byte range a
+/// downloader has in flight so the limit holds for the link as a whole.
+#[derive(Debug)]
+pub struct RateLimit {
+    bytes_per_sec: u64,
+    /// When the bytes received so far will have "paid off" at the limit.
+    next_free: Mutex<Instant>,
+}
+
+impl RateLimit {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec: bytes_per_sec.max(1),
+            next_free: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn bytes_per_sec(&self) -> u64 {
+        self.bytes_per_sec
+    }
+
+    /// Account for `bytes` just received, sleeping long enough to bring the
+    /// average back under the limit. Idle time isn't banked, so a stream
+    /// can't burst after a pause.
+    pub async fn throttle(&self, bytes: u64) {
+        let cost = Duration::from_secs_f64(bytes as f64 / self.bytes_per_sec as f64);
+        let wake = {
+            let mut next_free = self.next_free.lock().expect("rate limit poisoned");
+            *next_free = (*next_free).max(Instant::now()) + cost;
+            *next_free
+        };
+        tokio::time::sleep_until(wake).await;
+    }
+}
@@ -0,0 +1,351 @@
+use anyhow::Context;
+use flate2::read::MultiGzDecoder;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+use crate::Result;
+
+/// The first record that breaks coordinate order.
+#[derive(Debug, Clone)]
+pub struct OutOfOrder {
+    pub line: u64,
+    pub chrom: String,
+    pub pos: u64,
+    pub reason: String,
+}
+
+/// Stream a bgzipped VCF and confirm records are sorted by position within
+/// each contig, with contigs in the order the `##contig` header lines declare
+/// (or, without them, never revisited once left). Returns the first record
+/// that violates the order, if any.
+pub fn check_sorted(path: &Path) -> Result<Option<OutOfOrder>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let reader = BufReader::new(MultiGzDecoder::new(file));
+
+    let mut declared: HashMap<String, usize> = HashMap::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut current: Option<(String, usize, u64)> = None;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.with_context(|| format!("Failed to read {}", path.display()))?;
+        let line_number = index as u64 + 1;
+
+        if let Some(header) = line.strip_prefix("##contig=<") {
+            if let Some(id) = header
+                .trim_end_matches('>')
+                .split(',')
+                .find_map(|field| field.strip_prefix("ID="))
+            {
+                let rank = declared.len();
+                declared.entry(id.to_string()).or_insert(rank);
+            }
+            continue;
+        }
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+
+        let mut fields = line.splitn(3, '\t');
+        let chrom = fields.next().unwrap_or_default();
+        let pos: u64 = fields
+            .next()
+            .and_then(|pos| pos.parse().ok())
+            .with_context(|| format!("Line {} has no valid POS", line_number))?;
+
+        let out_of_order = |reason: String| {
+            Ok(Some(OutOfOrder {
+                line: line_number,
+                chrom: chrom.to_string(),
+                pos,
+                reason,
+            }))
+        };
+
+        match &mut current {
+            Some((current_chrom, _, last_pos)) if current_chrom == chrom => {
+                if pos < *last_pos {
+                    return out_of_order(format!(
+                        "position {} follows {} on the same contig",
+                        pos, last_pos
+                    ));
+                }
+                *last_pos = pos;
+            }
+            _ => {
+                let rank = if declared.is_empty() {
+                    seen.len()
+                } else {
+                    match declared.get(chrom) {
+                        Some(rank) => *rank,
+                        None => {
+                            return out_of_order(format!(
+                                "contig {} is not declared in the header",
+                                chrom
+                            ))
+                        }
+                    }
+                };
+
+                if seen.contains_key(chrom) {
+                    return out_of_order(format!(
+                        "contig {} appears again after other contigs",
+                        chrom
+                    ));
+                }
+                if let Some((previous, previous_rank, _)) = &current {
+                    if rank < *previous_rank {
+                        return out_of_order(format!(
+                            "contig {} is declared before {} but comes after it",
+                            chrom, previous
+                        ));
+                    }
+                }
+
+                seen.insert(chrom.to_string(), rank);
+                current = Some((chrom.to_string(), rank, pos));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Whether a tabix index (`.tbi` or `.csi`, chosen by extension) starts with
+/// the expected magic once decompressed. Files of any other kind pass.
+pub fn check_index(path: &Path) -> Result<bool> {
+    let magic: &[u8; 4] = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("tbi") => b"TBI\x01",
+        Some("csi") => b"CSI\x01",
+        _ => return Ok(true),
+    };
+
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut header = [0u8; 4];
+    match MultiGzDecoder::new(file).read_exact(&mut header) {
+        Ok(()) => Ok(&header == magic),
+        Err(_) => Ok(false),
+    }
+}
+
+/// Whether a file starts with the gzip magic bytes, as every bgzipped VCF
+/// does.
+pub fn is_gzip(path: &Path) -> Result<bool> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut header = [0u8; 2];
+    Ok(file.read_exact(&mut header).is_ok() && header == [0x1f, 0x8b])
+}
+
+/// Sequence names stored in a tabix index, in index order. `.tbi` files
+/// always carry them; `.csi` files carry them only when written by tabix, in
+/// the auxiliary data.
+pub fn index_sequence_names(path: &Path) -> Result<Vec<String>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut reader = MultiGzDecoder::new(file);
+
+    let read_i32 = |reader: &mut MultiGzDecoder<File>| -> Result<i32> {
+        let mut bytes = [0u8; 4];
+        reader
+            .read_exact(&mut bytes)
+            .with_context(|| format!("{} is truncated", path.display()))?;
+        Ok(i32::from_le_bytes(bytes))
+    };
+
+    let mut magic = [0u8; 4];
+    reader
+        .read_exact(&mut magic)
+        .with_context(|| format!("{} is truncated", path.display()))?;
+
+    // Both formats then share the tabix header: format, col_seq, col_beg,
+    // col_end, meta, skip, l_nm, names.
+    match &magic {
+        b"TBI\x01" => {
+            read_i32(&mut reader)?; // n_ref
+        }
+        b"CSI\x01" => {
+            read_i32(&mut reader)?; // min_shift
+            read_i32(&mut reader)?; // depth
+            if read_i32(&mut reader)? < 28 {
+                return Err(anyhow::anyhow!(
+                    "{} has no tabix header, so it carries no sequence names",
+                    path.display()
+                )
+                .into());
+            }
+        }
+        _ => return Err(anyhow::anyhow!("{} is not a tabix index", path.display()).into()),
+    }
+
+    for _ in 0..6 {
+        read_i32(&mut reader)?;
+    }
+    let names_len = usize::try_from(read_i32(&mut reader)?)
+        .map_err(|_| anyhow::anyhow!("{} has a negative name length", path.display()))?;
+    let mut names = vec![0u8; names_len];
+    reader
+        .read_exact(&mut names)
+        .with_context(|| format!("{} is truncated", path.display()))?;
+
+    Ok(names
+        .split(|&byte| byte == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .collect())
+}
+
+/// Contig IDs declared by `##contig` lines in a bgzipped VCF's header.
+pub fn header_contigs(path: &Path) -> Result<Vec<String>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let reader = BufReader::new(MultiGzDecoder::new(file));
+
+    let mut contigs = Vec::new();
+    for line in reader.lines() {
+        let line = line.with_context(|| format!("Failed to read {}", path.display()))?;
+        if !line.starts_with("##") {
+            break;
+        }
+        if let Some(id) = line.strip_prefix("##contig=<").and_then(|header| {
+            header
+                .trim_end_matches('>')
+                .split(',')
+                .find_map(|field| field.strip_prefix("ID="))
+        }) {
+            contigs.push(id.to_string());
+        }
+    }
+
+    Ok(contigs)
+}
+
+/// Check that an index belongs to a VCF: every sequence it indexes must be a
+/// contig the VCF declares, and it can't index more sequences than that.
+/// Returns a description of the mismatch, if any. A VCF without `##contig`
+/// lines can't be checked and passes.
+pub fn check_index_matches(vcf: &Path, index: &Path) -> Result<Option<String>> {
+    let contigs = header_contigs(vcf)?;
+    if contigs.is_empty() {
+        return Ok(None);
+    }
+    let names = index_sequence_names(index)?;
+
+    if names.len() > contigs.len() {
+        return Ok(Some(format!(
+            "index has {} sequences but the VCF declares {} contigs",
+            names.len(),
+            contigs.len()
+        )));
+    }
+
+    let unknown: Vec<&str> = names
+        .iter()
+        .filter(|name| !contigs.contains(name))
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        return Ok(Some(format!(
+            "index covers sequences the VCF doesn't declare: {}",
+            unknown.join(", ")
+        )));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bgzf::compress;
+
+    /// A bgzipped VCF declaring `contigs` in its header.
+    fn vcf(dir: &Path, contigs: &[&str]) -> std::path::PathBuf {
+        let mut text = String::from("##fileformat=VCFv4.2\n");
+        for contig in contigs {
+            text.push_str(&format!("##contig=<ID={},length=1000>\n", contig));
+        }
+        text.push_str("#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n");
+
+        let path = dir.join("clinvar.vcf.gz");
+        std::fs::write(&path, compress(text.as_bytes(), 1024)).unwrap();
+        path
+    }
+
+    /// A `.tbi` whose header indexes `names`, with no bins.
+    fn tbi(dir: &Path, names: &[&str]) -> std::path::PathBuf {
+        let n_ref = names.len() as i32;
+        let names: Vec<u8> = names
+            .iter()
+            .flat_map(|name| name.bytes().chain([0]))
+            .collect();
+        let mut index = b"TBI\x01".to_vec();
+        // n_ref, format, col_seq, col_beg, col_end, meta, skip, l_nm
+        for field in [n_ref, 2, 1, 2, 0, b'#' as i32, 0, names.len() as i32] {
+            index.extend_from_slice(&field.to_le_bytes());
+        }
+        index.extend_from_slice(&names);
+
+        let path = dir.join("clinvar.vcf.gz.tbi");
+        std::fs::write(&path, compress(&index, 1024)).unwrap();
+        path
+    }
+
+    #[test]
+    fn reads_sequence_names_from_a_tbi() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = tbi(dir.path(), &["1", "2", "X"]);
+
+        assert!(check_index(&index).unwrap());
+        assert_eq!(index_sequence_names(&index).unwrap(), ["1", "2", "X"]);
+    }
+
+    #[test]
+    fn matching_pair_passes() {
+        let dir = tempfile::tempdir().unwrap();
+        let vcf = vcf(dir.path(), &["1", "2", "X", "MT"]);
+        let index = tbi(dir.path(), &["1", "2", "X"]);
+
+        assert_eq!(check_index_matches(&vcf, &index).unwrap(), None);
+    }
+
+    #[test]
+    fn index_with_unknown_sequences_is_flagged() {
+        let dir = tempfile::tempdir().unwrap();
+        let vcf = vcf(dir.path(), &["chr1", "chr2"]);
+        let index = tbi(dir.path(), &["1", "chr2"]);
+
+        let mismatch = check_index_matches(&vcf, &index).unwrap().unwrap();
+
+        assert!(mismatch.contains("doesn't declare: 1"), "{}", mismatch);
+    }
+
+    #[test]
+    fn index_with_more_sequences_than_contigs_is_flagged() {
+        let dir = tempfile::tempdir().unwrap();
+        let vcf = vcf(dir.path(), &["1"]);
+        let index = tbi(dir.path(), &["1", "2"]);
+
+        let mismatch = check_index_matches(&vcf, &index).unwrap().unwrap();
+
+        assert!(mismatch.contains("2 sequences"), "{}", mismatch);
+    }
+
+    #[test]
+    fn vcf_without_contigs_is_not_checked() {
+        let dir = tempfile::tempdir().unwrap();
+        let vcf = vcf(dir.path(), &[]);
+        let index = tbi(dir.path(), &["1"]);
+
+        assert_eq!(check_index_matches(&vcf, &index).unwrap(), None);
+    }
+
+    #[test]
+    fn non_index_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("clinvar.vcf.gz.tbi");
+        std::fs::write(&path, compress(b"not an index", 1024)).unwrap();
+
+        assert!(!check_index(&path).unwrap());
+        assert!(index_sequence_names(&path).is_err());
+    }
+}